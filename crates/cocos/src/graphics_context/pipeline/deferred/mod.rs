@@ -1,6 +1,7 @@
 mod main_flow;
 
 use crate::Camera;
+use cocos_core::tracing::error;
 
 use super::{FrameGraphContext, RenderFlow, RenderPipeline};
 use cocos_renderer::{Device, FrameGraph, TransientResourceCache};
@@ -40,11 +41,14 @@ impl RenderPipeline for DeferredRenderPipeline {
             }
         }
 
-        self.fg
-            .compile(&self.device, &mut self.transient_resource_cache);
+        let result = self.fg.compile().and_then(|_| {
+            self.fg
+                .execute(&self.device, &mut self.transient_resource_cache)
+        });
 
-        self.fg
-            .execute(&self.device, &mut self.transient_resource_cache);
+        if let Err(err) = result {
+            error!("frame graph failed to render: {err}");
+        }
 
         self.fg.reset();
     }
@@ -1,6 +1,6 @@
 pub mod deferred;
 
-use cocos_renderer::FrameGraph;
+use cocos_renderer::{Blackboard, FrameGraph};
 
 use crate::Camera;
 
@@ -13,6 +13,17 @@ pub struct FrameGraphContext<'a> {
     pub camera: &'a Camera,
 }
 
+impl FrameGraphContext<'_> {
+    ///RenderFlow 通过黑板发布和查找其他 RenderFlow 创建的资源节点
+    pub fn blackboard(&self) -> &Blackboard {
+        self.fg.blackboard()
+    }
+
+    pub fn blackboard_mut(&mut self) -> &mut Blackboard {
+        self.fg.blackboard_mut()
+    }
+}
+
 pub trait RenderFlow: 'static {
     fn name(&self) -> &str;
 
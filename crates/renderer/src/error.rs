@@ -1,4 +1,86 @@
 use thiserror::Error;
 
+use crate::{Extent3d, PassKind, QueueType, TextureUsages};
+
 #[derive(Debug, Error)]
-pub enum RendererError {}
+pub enum RendererError {
+    #[error("frame graph has a dependency cycle between passes: {}", passes.join(", "))]
+    DependencyCycle { passes: Vec<String> },
+    #[error(
+        "pass `{pass}` reads version {version} of `{resource}` which is written later by pass `{writer}`"
+    )]
+    ReadBeforeWrite {
+        pass: String,
+        resource: String,
+        version: u32,
+        writer: String,
+    },
+    #[error("pass `{pass}` reads `{resource}` which was never written or imported")]
+    ReadUnwrittenResource { pass: String, resource: String },
+    #[error(
+        "version {version} of `{resource}` is written by both `{first_pass}` and `{second_pass}`"
+    )]
+    DuplicateWrite {
+        resource: String,
+        version: u32,
+        first_pass: String,
+        second_pass: String,
+    },
+    #[error(
+        "pass `{pass}` uses resource node {index} which does not belong to this frame graph, was it kept across FrameGraph::reset?"
+    )]
+    StaleResourceHandle { pass: String, index: usize },
+    #[error(
+        "{kind} handle {index} of generation {generation} does not belong to this frame graph (generation {expected}), was it kept across FrameGraph::reset?"
+    )]
+    InvalidHandle {
+        kind: &'static str,
+        index: usize,
+        generation: u32,
+        expected: u32,
+    },
+    #[error("FrameGraph::execute was called before FrameGraph::compile")]
+    NotCompiled,
+    #[error("pass `{pass}` accesses `{resource}` which it did not read or write")]
+    UndeclaredResourceAccess { pass: String, resource: String },
+    #[error(
+        "pass `{pass}` accesses `{resource}` which is not available, it was released or never requested"
+    )]
+    ResourceNotAvailable { pass: String, resource: String },
+    #[error("pass `{pass}` accesses `{resource}` as the wrong resource type")]
+    ResourceTypeMismatch { pass: String, resource: String },
+    #[error("pass `{pass}` cannot mutably access imported resource `{resource}`")]
+    ImportedResourceNotMutable { pass: String, resource: String },
+    #[error(
+        "pass `{pass}` cannot mutably access `{resource}` while recording in parallel, its device pass does not write it"
+    )]
+    SharedResourceNotMutable { pass: String, resource: String },
+    #[error("pass `{pass}` declares attachments but does not record into a render pass")]
+    AttachmentOutsideRenderPass { pass: String },
+    #[error("pass `{pass}` of kind {kind:?} cannot run on the {queue:?} queue")]
+    UnsupportedQueue {
+        pass: String,
+        kind: PassKind,
+        queue: QueueType,
+    },
+    #[error("pass `{pass}` records commands outside of FrameGraph::execute")]
+    CommandBufferNotAvailable { pass: String },
+    #[error("pass `{pass}` copies `{from}` of size {from_size:?} to `{to}` of size {to_size:?}")]
+    CopySizeMismatch {
+        pass: String,
+        from: String,
+        from_size: Extent3d,
+        to: String,
+        to_size: Extent3d,
+    },
+    #[error("pass `{pass}` writes read-only resource `{resource}`")]
+    ReadOnlyResourceWrite { pass: String, resource: String },
+    #[error("history resource `{name}` is declared more than once in a frame")]
+    DuplicateHistoryResource { name: String },
+    #[error(
+        "the previous frame's texture of history resource `{name}` was created without {usage:?}, its history restarts next frame"
+    )]
+    HistoryUsageNotSupported { name: String, usage: TextureUsages },
+    #[error("render module instance `{name}` is added more than once in a frame")]
+    DuplicateModuleInstance { name: String },
+}
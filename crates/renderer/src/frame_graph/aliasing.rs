@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+
+use crate::gfx_base::TypeHandle;
+
+use super::{AnyFGResourceDescriptor, PassNode, VirtualResource, VirtualResourceState};
+
+///一块被生命周期不重叠的临时资源共享的内存
+#[derive(Debug, Clone)]
+pub struct TransientSlot {
+    ///能够满足所有共享资源的描述
+    pub desc: AnyFGResourceDescriptor,
+    ///按首次使用顺序排列的共享资源
+    pub resources: Vec<TypeHandle<VirtualResource>>,
+    ///最后使用此内存的 DevicePass 在执行顺序中的位置
+    last_order: usize,
+}
+
+///单帧临时资源的显存统计
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransientMemoryReport {
+    pub transient_count: usize,
+    pub slot_count: usize,
+    ///每个临时资源单独分配时的显存峰值
+    pub bytes_without_aliasing: u64,
+    ///临时资源共享内存后的显存峰值
+    pub bytes_with_aliasing: u64,
+}
+
+///根据资源生命周期为临时资源分配共享内存
+#[derive(Debug, Clone, Default)]
+pub struct AliasingPlan {
+    slots: Vec<TransientSlot>,
+    resource_slots: HashMap<TypeHandle<VirtualResource>, usize>,
+    report: TransientMemoryReport,
+}
+
+impl AliasingPlan {
+    ///groups 为按执行顺序排列的 DevicePass，合并的渲染节点在开始之前申请所有子通道的资源，
+    ///所以同一 DevicePass 中的资源生命周期互相重叠
+    pub fn new(
+        groups: &[Vec<TypeHandle<PassNode>>],
+        resources: &[VirtualResource],
+    ) -> AliasingPlan {
+        let orders: HashMap<TypeHandle<PassNode>, usize> = groups
+            .iter()
+            .enumerate()
+            .flat_map(|(order, group)| group.iter().map(move |handle| (*handle, order)))
+            .collect();
+
+        let mut transients = resources
+            .iter()
+            .filter_map(|resource| {
+                let VirtualResourceState::Setup(desc) = &resource.state else {
+                    return None;
+                };
+
+                if resource.persistent || resource.cross_queue {
+                    return None;
+                }
+
+                let first_order = orders.get(&resource.info.first_pass_node_handle?)?;
+                let last_order = orders.get(&resource.info.last_pass_node_handle?)?;
+
+                Some((*first_order, *last_order, resource.info.handle, desc))
+            })
+            .collect::<Vec<_>>();
+
+        transients.sort_by_key(|(first_order, _, handle, _)| (*first_order, handle.index()));
+
+        let mut plan = AliasingPlan::default();
+        //每个临时资源的生命周期和所在的共享内存
+        let mut lifetimes = vec![];
+
+        for (first_order, last_order, handle, desc) in transients {
+            plan.report.transient_count += 1;
+
+            let free_slot = plan.slots.iter().position(|slot| {
+                slot.last_order < first_order && slot.desc.is_alias_compatible(desc)
+            });
+
+            let slot_index = match free_slot {
+                Some(slot_index) => {
+                    let slot = &mut plan.slots[slot_index];
+                    slot.desc.merge(desc);
+                    slot.resources.push(handle);
+                    slot.last_order = last_order;
+
+                    slot_index
+                }
+                None => {
+                    plan.slots.push(TransientSlot {
+                        desc: desc.clone(),
+                        resources: vec![handle],
+                        last_order,
+                    });
+
+                    plan.slots.len() - 1
+                }
+            };
+
+            plan.resource_slots.insert(handle, slot_index);
+            lifetimes.push((first_order, last_order, desc.size_in_bytes(), slot_index));
+        }
+
+        plan.report.slot_count = plan.slots.len();
+
+        //在每个 DevicePass 执行时统计存活的资源，取整帧的峰值
+        for order in 0..groups.len() {
+            let live = lifetimes
+                .iter()
+                .filter(|(first_order, last_order, _, _)| {
+                    (*first_order..=*last_order).contains(&order)
+                })
+                .collect::<Vec<_>>();
+
+            let bytes_without_aliasing = live.iter().map(|(_, _, size, _)| size).sum();
+
+            //同时存活的资源不会共享内存，每个资源对应一块不同的共享内存
+            let bytes_with_aliasing = live
+                .iter()
+                .map(|(_, _, _, slot_index)| plan.slots[*slot_index].desc.size_in_bytes())
+                .sum();
+
+            plan.report.bytes_without_aliasing = plan
+                .report
+                .bytes_without_aliasing
+                .max(bytes_without_aliasing);
+            plan.report.bytes_with_aliasing =
+                plan.report.bytes_with_aliasing.max(bytes_with_aliasing);
+        }
+
+        plan
+    }
+
+    pub fn slots(&self) -> &[TransientSlot] {
+        &self.slots
+    }
+
+    pub fn get_slot(&self, handle: &TypeHandle<VirtualResource>) -> Option<&TransientSlot> {
+        self.resource_slots
+            .get(handle)
+            .map(|slot_index| &self.slots[*slot_index])
+    }
+
+    ///资源使用的共享内存在 slots 中的位置
+    pub fn get_slot_index(&self, handle: &TypeHandle<VirtualResource>) -> Option<usize> {
+        self.resource_slots.get(handle).copied()
+    }
+
+    pub fn report(&self) -> &TransientMemoryReport {
+        &self.report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BufferDescriptor, BufferUsages, FrameGraph, PassKind, TransientMemoryReport};
+
+    use super::super::testing::{add_pass, buffer_desc};
+
+    ///a -> b -> c -> d 依次传递缓冲区，present 读取最后的结果。
+    ///keep_first 为 true 时 present 同时读取第一个缓冲区，mapped 中的缓冲区可以映射读取
+    fn build_chain(keep_first: bool, mapped: &[&str]) -> FrameGraph {
+        let mut fg = FrameGraph::default();
+        let names = ["a", "b", "c", "d"];
+        let buffers = names.map(|name| {
+            let desc = if mapped.contains(&name) {
+                BufferDescriptor {
+                    usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+                    ..buffer_desc()
+                }
+            } else {
+                buffer_desc()
+            };
+
+            fg.create(name, desc)
+        });
+
+        let first = add_pass(&mut fg, 0, "a", PassKind::Compute, |builder| {
+            builder.write(buffers[0].clone()).handle()
+        });
+
+        let mut previous = first.clone();
+        for (index, name) in names.iter().enumerate().skip(1) {
+            let buffer = buffers[index].clone();
+            previous = add_pass(&mut fg, index, name, PassKind::Compute, |builder| {
+                builder.read(previous);
+                builder.write(buffer).handle()
+            });
+        }
+
+        add_pass(&mut fg, 4, "present", PassKind::Compute, |builder| {
+            builder.read(previous);
+            if keep_first {
+                builder.read(first);
+            }
+            builder.side_effect();
+        });
+
+        fg.compile().unwrap();
+        fg
+    }
+
+    fn slot_resources(fg: &FrameGraph) -> Vec<Vec<&str>> {
+        fg.aliasing_plan()
+            .slots()
+            .iter()
+            .map(|slot| {
+                slot.resources
+                    .iter()
+                    .map(|handle| fg.resource(handle).info.name.as_str())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn disjoint_lifetimes_share_slots() {
+        let fg = build_chain(false, &[]);
+
+        assert_eq!(slot_resources(&fg), vec![vec!["a", "c"], vec!["b", "d"]]);
+
+        //任意时刻最多两个缓冲区同时存活
+        assert_eq!(
+            *fg.transient_memory_report(),
+            TransientMemoryReport {
+                transient_count: 4,
+                slot_count: 2,
+                bytes_without_aliasing: 512,
+                bytes_with_aliasing: 512,
+            }
+        );
+    }
+
+    #[test]
+    fn overlapping_lifetimes_do_not_share_slots() {
+        let fg = build_chain(true, &[]);
+
+        assert_eq!(
+            slot_resources(&fg),
+            vec![vec!["a"], vec!["b", "d"], vec!["c"]]
+        );
+
+        //c 执行时 a、b、c 同时存活，d 复用 b 的内存
+        assert_eq!(
+            *fg.transient_memory_report(),
+            TransientMemoryReport {
+                transient_count: 4,
+                slot_count: 3,
+                bytes_without_aliasing: 768,
+                bytes_with_aliasing: 768,
+            }
+        );
+    }
+
+    #[test]
+    fn mappable_buffers_do_not_share_slots() {
+        let fg = build_chain(false, &["c"]);
+
+        //c 的生命周期与 a 不重叠，但是映射的内存不能被其他资源覆盖，d 复用 a 的内存
+        assert_eq!(
+            slot_resources(&fg),
+            vec![vec!["a", "d"], vec!["b"], vec!["c"]]
+        );
+        assert_eq!(fg.transient_memory_report().slot_count, 3);
+    }
+}
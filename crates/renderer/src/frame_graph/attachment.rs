@@ -0,0 +1,21 @@
+use crate::{Color, Operations, TextureSubresourceRange, gfx_base::TypeHandle};
+
+use super::VirtualResource;
+
+///渲染节点声明的颜色附件
+#[derive(Debug, Clone)]
+pub struct ColorAttachmentInfo {
+    pub texture: TypeHandle<VirtualResource>,
+    pub range: TextureSubresourceRange,
+    pub resolve_target: Option<TypeHandle<VirtualResource>>,
+    pub ops: Operations<Color>,
+}
+
+///渲染节点声明的深度模板附件
+#[derive(Debug, Clone)]
+pub struct DepthStencilAttachmentInfo {
+    pub texture: TypeHandle<VirtualResource>,
+    pub range: TextureSubresourceRange,
+    pub depth_ops: Option<Operations<f32>>,
+    pub stencil_ops: Option<Operations<u32>>,
+}
@@ -0,0 +1,13 @@
+use crate::{ResourceUsage, TextureSubresourceRange, gfx_base::TypeHandle};
+
+use super::VirtualResource;
+
+///compile 时计算的资源状态转换，在渲染节点执行前录制
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BarrierInfo {
+    pub resource: TypeHandle<VirtualResource>,
+    ///缓冲区总是使用 TextureSubresourceRange::ALL
+    pub range: TextureSubresourceRange,
+    pub before: ResourceUsage,
+    pub after: ResourceUsage,
+}
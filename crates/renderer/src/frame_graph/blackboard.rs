@@ -0,0 +1,145 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+};
+
+use super::ResourceNodeHandle;
+
+///在 RenderFlow 之间共享资源节点和数据，FrameGraph reset 时清空
+#[derive(Default)]
+pub struct Blackboard {
+    ///按名称保存的资源节点
+    handles: HashMap<String, Box<dyn Any>>,
+    ///按类型保存的数据
+    values: HashMap<TypeId, Box<dyn Any>>,
+}
+
+impl Blackboard {
+    ///同名的资源节点会被覆盖，写入资源后应该发布新的资源节点
+    pub fn put<ResourceType: 'static>(
+        &mut self,
+        name: &str,
+        handle: ResourceNodeHandle<ResourceType>,
+    ) {
+        self.handles.insert(name.to_string(), Box::new(handle));
+    }
+
+    ///名称不存在或者资源类型不匹配时返回 None
+    pub fn get<ResourceType: 'static>(
+        &self,
+        name: &str,
+    ) -> Option<ResourceNodeHandle<ResourceType>> {
+        self.handles
+            .get(name)
+            .and_then(|handle| handle.downcast_ref::<ResourceNodeHandle<ResourceType>>())
+            .cloned()
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.handles.contains_key(name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.handles.remove(name).is_some()
+    }
+
+    pub fn put_value<T: 'static>(&mut self, value: T) {
+        self.values.insert(TypeId::of::<T>(), Box::new(value));
+    }
+
+    pub fn get_value<T: 'static>(&self) -> Option<&T> {
+        self.values
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref())
+    }
+
+    pub fn get_value_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.values
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_mut())
+    }
+
+    pub fn remove_value<T: 'static>(&mut self) -> Option<T> {
+        self.values
+            .remove(&TypeId::of::<T>())
+            .and_then(|value| value.downcast().ok())
+            .map(|value| *value)
+    }
+
+    pub fn clear(&mut self) {
+        self.handles.clear();
+        self.values.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Buffer, FrameGraph, Texture};
+
+    use super::{
+        super::testing::{buffer_desc, texture_desc},
+        Blackboard,
+    };
+
+    #[derive(Debug, PartialEq)]
+    struct Exposure(f32);
+
+    #[derive(Debug, PartialEq)]
+    struct FrameIndex(u32);
+
+    #[test]
+    fn values_are_keyed_by_type() {
+        let mut blackboard = Blackboard::default();
+        blackboard.put_value(Exposure(1.5));
+        blackboard.put_value(FrameIndex(7));
+
+        assert_eq!(blackboard.get_value::<Exposure>(), Some(&Exposure(1.5)));
+        assert_eq!(blackboard.get_value::<FrameIndex>(), Some(&FrameIndex(7)));
+
+        //同一类型只保存一个值
+        blackboard.put_value(Exposure(2.0));
+        blackboard.get_value_mut::<FrameIndex>().unwrap().0 += 1;
+        assert_eq!(blackboard.get_value::<Exposure>(), Some(&Exposure(2.0)));
+        assert_eq!(blackboard.remove_value::<FrameIndex>(), Some(FrameIndex(8)));
+
+        assert_eq!(blackboard.get_value::<FrameIndex>(), None);
+        assert_eq!(blackboard.get_value::<u32>(), None);
+    }
+
+    #[test]
+    fn handles_are_keyed_by_name_and_type() {
+        let mut fg = FrameGraph::default();
+        let first = fg.create("color", texture_desc(64));
+        let second = fg.create("color", texture_desc(64));
+        let buffer = fg.create("data", buffer_desc());
+
+        let blackboard = fg.blackboard_mut();
+        blackboard.put("color", first);
+        blackboard.put("color", second.clone());
+        blackboard.put("data", buffer);
+
+        //同名的资源节点被覆盖
+        let color = blackboard.get::<Texture>("color").unwrap();
+        assert_eq!(color.resource_node_handle(), second.resource_node_handle());
+
+        //名称不存在或者类型不匹配
+        assert!(blackboard.get::<Texture>("depth").is_none());
+        assert!(blackboard.get::<Buffer>("color").is_none());
+        assert!(blackboard.has("data"));
+        assert!(blackboard.remove("data"));
+        assert!(!blackboard.has("data"));
+    }
+
+    #[test]
+    fn reset_clears_the_blackboard() {
+        let mut fg = FrameGraph::default();
+        let color = fg.create("color", texture_desc(64));
+        fg.blackboard_mut().put("color", color);
+        fg.blackboard_mut().put_value(Exposure(1.0));
+
+        fg.reset();
+
+        assert!(!fg.blackboard().has("color"));
+        assert!(fg.blackboard().get_value::<Exposure>().is_none());
+    }
+}
@@ -1,25 +1,23 @@
+use crate::error::RendererError;
+
 use super::{Pass, PassNodeBuilder, render_context::RenderContext};
 
-pub type SetupFn<Data> = Box<dyn FnOnce(&mut PassNodeBuilder, &mut Data)>;
-pub type ExecuteFn<Data> = Box<dyn FnOnce(&Data, &mut RenderContext)>;
+pub type ExecuteFn<Data> =
+    Box<dyn FnOnce(&Data, &mut RenderContext) -> Result<(), RendererError> + Send>;
 
+///data 由 FrameGraph::add_callback_pass 的 setup 闭包填写
 pub struct CallbackPass<Data> {
     data: Data,
-    setup: Option<SetupFn<Data>>,
     execute: Option<ExecuteFn<Data>>,
 }
 
-impl<Data> CallbackPass<Data>
-where
-    Data: Default,
-{
+impl<Data> CallbackPass<Data> {
     pub fn new(
-        setup: impl FnOnce(&mut PassNodeBuilder, &mut Data) + 'static,
-        execute: impl FnOnce(&Data, &mut RenderContext) + 'static,
+        data: Data,
+        execute: impl FnOnce(&Data, &mut RenderContext) -> Result<(), RendererError> + Send + 'static,
     ) -> Self {
         CallbackPass {
-            data: Data::default(),
-            setup: Some(Box::new(setup)),
+            data,
             execute: Some(Box::new(execute)),
         }
     }
@@ -27,17 +25,14 @@ where
 
 impl<Data> Pass for CallbackPass<Data>
 where
-    Data: Default,
+    Data: Send,
 {
-    fn setup(&mut self, builder: &mut PassNodeBuilder) {
-        if let Some(setup) = self.setup.take() {
-            setup(builder, &mut self.data);
-        }
-    }
+    fn setup(&mut self, _builder: &mut PassNodeBuilder) {}
 
-    fn execute(&mut self, render_context: &mut RenderContext) {
-        if let Some(execute) = self.execute.take() {
-            execute(&self.data, render_context);
+    fn execute(&mut self, render_context: &mut RenderContext) -> Result<(), RendererError> {
+        match self.execute.take() {
+            Some(execute) => execute(&self.data, render_context),
+            None => Ok(()),
         }
     }
 }
@@ -1,85 +1,507 @@
-use std::mem::swap;
+use std::time::Instant;
 
-use crate::{RenderPassInfo, TypeHandle};
+use cocos_core::tracing::info_span;
+
+use crate::{
+    ColorAttachment, DepthStencilAttachment, RenderPassInfo, ResourceBarrier, Texture, TypeHandle,
+    error::RendererError,
+};
 
 use super::{
-    DynPass, FrameGraph, PassNode, RenderContext, ResourceTable, TransientResourceCache,
-    VirtualResource,
+    BarrierInfo, ColorAttachmentInfo, DepthStencilAttachmentInfo, DynPass, FrameGraph, PassKind,
+    PassNode, QueueSync, RenderContext, ResourceRequest, VirtualResource,
 };
 
+#[derive(Default)]
 pub struct DevicePass {
+    kind: PassKind,
+    ///合并时每个 LogicPass 对应一个子通道
     logic_passes: Vec<LogicPass>,
-    resource_table: ResourceTable,
+    color_attachments: Vec<ColorAttachmentInfo>,
+    depth_stencil_attachment: Option<DepthStencilAttachmentInfo>,
+    ///提交的队列和需要等待或发出的信号
+    queue_sync: QueueSync,
+    ///第一个子通道的时间戳查询，每个子通道使用两个
+    first_timestamp: u32,
 }
 
 pub struct LogicPass {
+    name: String,
     pass: DynPass,
+    ///声明读写的资源
+    resource_handles: Vec<TypeHandle<VirtualResource>>,
+    ///声明写入的资源
+    write_handles: Vec<TypeHandle<VirtualResource>>,
+    ///执行前需要申请的资源
+    resource_request_array: Vec<ResourceRequest>,
     resource_release_array: Vec<TypeHandle<VirtualResource>>,
+    barriers: Vec<BarrierInfo>,
 }
 
 impl LogicPass {
-    pub fn release_resources(
-        &mut self,
-        resource_table: &mut ResourceTable,
-        transient_resource_cache: &mut TransientResourceCache,
-    ) {
+    pub fn request_resources(&self, render_context: &mut RenderContext) {
+        for request in self.resource_request_array.iter() {
+            render_context.request_resource(request);
+        }
+    }
+
+    pub fn release_resources(&mut self, render_context: &mut RenderContext) {
         for handle in self.resource_release_array.iter() {
-            resource_table.release_resource(handle, transient_resource_cache);
+            render_context.release_resource(handle);
         }
     }
 }
 
 impl DevicePass {
-    pub fn new(resource_table: ResourceTable) -> DevicePass {
-        Self {
-            logic_passes: vec![],
-            resource_table,
+    pub fn new(queue_sync: QueueSync, first_timestamp: u32) -> Self {
+        DevicePass {
+            queue_sync,
+            first_timestamp,
+            ..Default::default()
         }
     }
 
+    ///所有子通道使用的时间戳查询数量
+    pub fn timestamp_count(&self) -> u32 {
+        2 * self.logic_passes.len() as u32
+    }
+
+    pub fn queue_sync(&self) -> &QueueSync {
+        &self.queue_sync
+    }
+
     pub fn extra(&mut self, fg: &mut FrameGraph, handle: TypeHandle<PassNode>) {
-        let pass_node = fg.get_pass_node_mut(&handle);
+        let pass_node = fg.pass_node(&handle);
+
+        let mut resource_handles: Vec<TypeHandle<VirtualResource>> = vec![];
+        for resource_node_handle in pass_node.reads.iter().chain(pass_node.writes.iter()) {
+            let resource_handle = fg.resource_node(resource_node_handle).resource_handle;
+
+            if !resource_handles.contains(&resource_handle) {
+                resource_handles.push(resource_handle);
+            }
+        }
+
+        let write_handles = pass_node
+            .writes
+            .iter()
+            .map(|resource_node_handle| fg.resource_node(resource_node_handle).resource_handle)
+            .collect();
+
+        let resource_request_array = pass_node
+            .resource_request_array
+            .iter()
+            .map(|resource_handle| fg.create_resource_request(resource_handle))
+            .collect();
+
+        let pass_node = fg.pass_node_mut(&handle);
+
+        if self.logic_passes.is_empty() {
+            self.kind = pass_node.kind;
+            self.color_attachments = pass_node.color_attachments.clone();
+            self.depth_stencil_attachment = pass_node.depth_stencil_attachment.clone();
+        } else {
+            self.merge_store_ops(pass_node);
+        }
 
         let logic_pass = LogicPass {
+            name: pass_node.name.clone(),
             pass: pass_node.pass.take().unwrap(),
+            resource_handles,
+            write_handles,
+            resource_request_array,
             resource_release_array: pass_node.resource_release_array.clone(),
+            barriers: pass_node.barriers.clone(),
         };
 
         self.logic_passes.push(logic_pass);
     }
 
-    pub fn execute(&mut self, render_context: &mut RenderContext) {
-        self.begin(render_context);
+    ///子通道的加载操作由第一个渲染节点决定，存储操作由最后一个渲染节点决定
+    fn merge_store_ops(&mut self, pass_node: &PassNode) {
+        for (attachment, other) in self
+            .color_attachments
+            .iter_mut()
+            .zip(pass_node.color_attachments.iter())
+        {
+            attachment.ops.store = other.ops.store;
+        }
 
-        for logic_pass in self.logic_passes.iter_mut() {
-            logic_pass.pass.execute(render_context);
+        if let (Some(attachment), Some(other)) = (
+            self.depth_stencil_attachment.as_mut(),
+            pass_node.depth_stencil_attachment.as_ref(),
+        ) {
+            if let (Some(ops), Some(other)) = (attachment.depth_ops.as_mut(), other.depth_ops) {
+                ops.store = other.store;
+            }
 
-            logic_pass.release_resources(
-                &mut render_context.resource_table,
-                render_context.transient_resource_cache,
-            );
+            if let (Some(ops), Some(other)) = (attachment.stencil_ops.as_mut(), other.stencil_ops) {
+                ops.store = other.store;
+            }
+        }
+    }
+
+    ///所有子通道执行前需要申请的资源
+    pub(crate) fn resource_requests(&self) -> impl Iterator<Item = &ResourceRequest> {
+        self.logic_passes
+            .iter()
+            .flat_map(|logic_pass| logic_pass.resource_request_array.iter())
+    }
+
+    ///所有子通道声明读写的资源
+    pub(crate) fn resource_handles(&self) -> impl Iterator<Item = &TypeHandle<VirtualResource>> {
+        self.logic_passes
+            .iter()
+            .flat_map(|logic_pass| logic_pass.resource_handles.iter())
+    }
+
+    ///所有子通道声明写入的资源
+    pub(crate) fn write_handles(&self) -> impl Iterator<Item = &TypeHandle<VirtualResource>> {
+        self.logic_passes
+            .iter()
+            .flat_map(|logic_pass| logic_pass.write_handles.iter())
+    }
+
+    ///所有子通道执行后可以释放的资源
+    pub(crate) fn resource_releases(&self) -> impl Iterator<Item = &TypeHandle<VirtualResource>> {
+        self.logic_passes
+            .iter()
+            .flat_map(|logic_pass| logic_pass.resource_release_array.iter())
+    }
+
+    pub fn execute(&mut self, render_context: &mut RenderContext) -> Result<(), RendererError> {
+        //屏障在渲染通道开始之前录制，需要先申请所有子通道的资源
+        for logic_pass in self.logic_passes.iter() {
+            logic_pass.request_resources(render_context);
+        }
+
+        if let Some(logic_pass) = self.logic_passes.first() {
+            render_context.set_pass(&logic_pass.name, &logic_pass.resource_handles);
+        }
+
+        self.begin(render_context)?;
+
+        for (index, logic_pass) in self.logic_passes.iter_mut().enumerate() {
+            if index > 0 {
+                render_context.next_subpass();
+            }
+
+            let _span = info_span!("pass", name = %logic_pass.name).entered();
+            let timestamp_index = self.first_timestamp + 2 * index as u32;
+            let start = Instant::now();
+            render_context.push_debug_group(&logic_pass.name);
+            render_context.write_timestamp(timestamp_index);
+
+            render_context.set_pass(&logic_pass.name, &logic_pass.resource_handles);
+            logic_pass.pass.execute(render_context)?;
+
+            render_context.write_timestamp(timestamp_index + 1);
+            render_context.pop_debug_group();
+            render_context.push_pass_timing(&logic_pass.name, start.elapsed(), timestamp_index);
         }
 
         self.end(render_context);
-    }
 
-    pub fn begin(&mut self, render_context: &mut RenderContext) {
-        swap(&mut self.resource_table, &mut render_context.resource_table);
+        //子通道的资源在渲染通道结束之前一直被使用
+        for logic_pass in self.logic_passes.iter_mut() {
+            logic_pass.release_resources(render_context);
+        }
+
+        Ok(())
+    }
 
+    pub fn begin(&self, render_context: &mut RenderContext) -> Result<(), RendererError> {
         let mut command_buffer = render_context.device().create_command_buffer();
 
-        let render_pass_info: RenderPassInfo = RenderPassInfo::new();
-        let render_pass = render_context.device().create_render_pass(render_pass_info);
-        command_buffer.begin_render_pass(render_pass);
+        let barriers = self.create_barriers(render_context);
+        if !barriers.is_empty() {
+            command_buffer.pipeline_barrier(&barriers);
+        }
+
+        match self.kind {
+            PassKind::Render => {
+                let render_pass_info = self.create_render_pass_info(render_context)?;
+                let render_pass = render_context.device().create_render_pass(render_pass_info);
+                command_buffer.begin_render_pass(render_pass);
+            }
+            PassKind::Compute => {
+                command_buffer.begin_compute_pass();
+            }
+            PassKind::Transfer => {}
+        }
 
         render_context.set_cb(command_buffer);
+
+        Ok(())
     }
 
-    pub fn end(&self, render_context: &mut RenderContext) {
-        render_context.resource_table = ResourceTable::default();
+    ///所有子通道的屏障都在渲染通道开始之前录制，附件之间的同步由子通道依赖处理
+    fn create_barriers<'a>(&self, render_context: &'a RenderContext) -> Vec<ResourceBarrier<'a>> {
+        let attachments = self
+            .color_attachments
+            .iter()
+            .flat_map(|attachment| {
+                std::iter::once(attachment.texture).chain(attachment.resolve_target)
+            })
+            .chain(
+                self.depth_stencil_attachment
+                    .iter()
+                    .map(|attachment| attachment.texture),
+            )
+            .collect::<Vec<_>>();
+
+        self.logic_passes
+            .iter()
+            .enumerate()
+            .flat_map(|(index, logic_pass)| {
+                let attachments = &attachments;
+
+                logic_pass
+                    .barriers
+                    .iter()
+                    .filter(move |barrier| index == 0 || !attachments.contains(&barrier.resource))
+            })
+            .filter_map(|barrier| render_context.create_barrier(barrier))
+            .collect()
+    }
+
+    fn create_render_pass_info<'a>(
+        &self,
+        render_context: &'a RenderContext,
+    ) -> Result<RenderPassInfo<'a>, RendererError> {
+        let mut render_pass_info = RenderPassInfo::new();
 
+        for color_attachment in self.color_attachments.iter() {
+            let resolve_target = match &color_attachment.resolve_target {
+                Some(handle) => Some(render_context.get_resource::<Texture>(handle)?),
+                None => None,
+            };
+
+            render_pass_info.color_attachments.push(ColorAttachment {
+                view: render_context.get_resource(&color_attachment.texture)?,
+                range: color_attachment.range,
+                resolve_target,
+                ops: color_attachment.ops,
+            });
+        }
+
+        if let Some(depth_stencil_attachment) = &self.depth_stencil_attachment {
+            render_pass_info.depth_stencil_attachment = Some(DepthStencilAttachment {
+                view: render_context.get_resource(&depth_stencil_attachment.texture)?,
+                range: depth_stencil_attachment.range,
+                depth_ops: depth_stencil_attachment.depth_ops,
+                stencil_ops: depth_stencil_attachment.stencil_ops,
+            });
+        }
+
+        Ok(render_pass_info)
+    }
+
+    pub fn end(&self, render_context: &mut RenderContext) {
         if let Some(mut command_buffer) = render_context.take_cb() {
-            command_buffer.end_render_pass();
+            match self.kind {
+                PassKind::Render => command_buffer.end_render_pass(),
+                PassKind::Compute => command_buffer.end_compute_pass(),
+                PassKind::Transfer => {}
+            }
+
+            render_context.push_command_buffer(self.queue_sync.clone(), command_buffer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        CompileOptions, Device, FrameGraph, LoadOp, Operations, PassKind, StoreOp,
+        TransientResourceCache,
+    };
+
+    use super::super::testing::{RecordingDevice, add_pass, buffer_desc, texture_desc};
+
+    #[test]
+    fn resources_are_labeled_and_passes_are_grouped() {
+        let mut fg = FrameGraph::default();
+        let color = fg.create("color", texture_desc(64));
+        let data = fg.create("data", buffer_desc());
+
+        let color = fg.add_callback_pass(
+            0,
+            "draw",
+            |builder, _: &mut ()| {
+                let color = builder.write(color);
+                builder.add_color_attachment(&color, Default::default());
+                color.handle()
+            },
+            |_, render_context| {
+                render_context.insert_debug_marker("draw call");
+                Ok(())
+            },
+        );
+
+        add_pass(&mut fg, 1, "post", PassKind::Compute, |builder| {
+            builder.read(color);
+            builder.write(data);
+            builder.side_effect();
+        });
+
+        let recording = RecordingDevice::default();
+        fg.compile().unwrap();
+        fg.execute(
+            &Device::new(recording.clone()),
+            &mut TransientResourceCache::default(),
+        )
+        .unwrap();
+
+        let log = recording
+            .take_log()
+            .into_iter()
+            .filter(|entry| entry.starts_with("create_") || entry.contains("debug"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            log,
+            [
+                "create_texture color",
+                "push_debug_group draw",
+                "insert_debug_marker draw call",
+                "pop_debug_group",
+                "create_buffer data",
+                "push_debug_group post",
+                "pop_debug_group",
+            ]
+        );
+    }
+
+    #[test]
+    fn compute_passes_record_into_a_compute_pass() {
+        let mut fg = FrameGraph::default();
+        let particles = fg.create("particles", buffer_desc());
+
+        fg.add_compute_pass(
+            0,
+            "simulate",
+            |builder, _: &mut ()| {
+                builder.write(particles);
+                builder.side_effect();
+            },
+            |_, render_context| {
+                render_context.insert_debug_marker("dispatch");
+                Ok(())
+            },
+        );
+
+        let recording = RecordingDevice::default();
+        fg.compile().unwrap();
+        fg.execute(
+            &Device::new(recording.clone()),
+            &mut TransientResourceCache::default(),
+        )
+        .unwrap();
+
+        let log = recording
+            .take_log()
+            .into_iter()
+            .filter(|entry| entry.ends_with("_pass") || entry.contains("debug"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            log,
+            [
+                "begin_compute_pass",
+                "push_debug_group simulate",
+                "insert_debug_marker dispatch",
+                "pop_debug_group",
+                "end_compute_pass",
+            ]
+        );
+    }
+
+    ///opaque 清除并解析多重采样颜色附件，transparent 加载之后丢弃多重采样和深度的内容
+    #[test]
+    fn merged_render_pass_records_attachments_and_barriers() {
+        let mut fg = FrameGraph::default();
+        fg.set_compile_options(CompileOptions {
+            merge_passes: true,
+            ..Default::default()
+        });
+
+        let lut = fg.create("lut", texture_desc(64));
+        let noise = fg.create("noise", texture_desc(64));
+        let msaa = fg.create("msaa", texture_desc(64));
+        let resolved = fg.create("resolved", texture_desc(64));
+        let depth = fg.create("ds", texture_desc(64));
+        fn load_and_discard<V>() -> Operations<V> {
+            Operations {
+                load: LoadOp::Load,
+                store: StoreOp::Discard,
+            }
         }
+
+        let lut = add_pass(&mut fg, 0, "lut", PassKind::Compute, |builder| {
+            builder.write(lut).handle()
+        });
+        let noise = add_pass(&mut fg, 1, "noise", PassKind::Compute, |builder| {
+            builder.write(noise).handle()
+        });
+        let (msaa, resolved, depth) = add_pass(&mut fg, 2, "opaque", PassKind::Render, |builder| {
+            builder.read(lut);
+            let msaa = builder.write(msaa);
+            let resolved = builder.write(resolved);
+            let depth = builder.write(depth);
+            builder.add_color_attachment_with_resolve(&msaa, &resolved, Operations::default());
+            builder.set_depth_stencil_attachment(
+                &depth,
+                Some(Operations::default()),
+                Some(Operations::default()),
+            );
+            (msaa.handle(), resolved.handle(), depth.handle())
+        });
+        let resolved = add_pass(&mut fg, 3, "transparent", PassKind::Render, |builder| {
+            builder.read(noise);
+            builder.read(msaa.clone());
+            builder.read(depth.clone());
+            let msaa = builder.write(msaa);
+            let resolved = builder.write(resolved);
+            let depth = builder.write(depth);
+            builder.add_color_attachment_with_resolve(&msaa, &resolved, load_and_discard());
+            builder.set_depth_stencil_attachment(&depth, Some(load_and_discard()), None);
+            resolved.handle()
+        });
+        add_pass(&mut fg, 4, "present", PassKind::Compute, |builder| {
+            builder.read(resolved);
+            builder.side_effect();
+        });
+
+        let recording = RecordingDevice::default();
+        fg.compile().unwrap();
+        assert_eq!(fg.merged_passes(), [vec!["opaque", "transparent"]]);
+        fg.execute(
+            &Device::new(recording.clone()),
+            &mut TransientResourceCache::default(),
+        )
+        .unwrap();
+
+        let log = recording
+            .take_log()
+            .into_iter()
+            .filter(|entry| entry.starts_with("barrier") || entry.starts_with("render_pass_info"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            log,
+            [
+                "barrier lut Undefined -> StorageWrite",
+                "barrier noise Undefined -> StorageWrite",
+                //第一个子通道的屏障包括附件的初始转换
+                "barrier lut StorageWrite -> Sampled",
+                "barrier msaa Undefined -> ColorAttachment",
+                "barrier resolved Undefined -> ColorAttachment",
+                "barrier ds Undefined -> DepthStencilWrite",
+                //之后的子通道只保留附件以外的屏障
+                "barrier noise StorageWrite -> Sampled",
+                //加载操作来自 opaque，存储操作来自 transparent，模板只由 opaque 写入
+                "render_pass_info color msaa clear/discard resolve resolved, \
+                 depth ds depth clear/discard stencil clear/store",
+                "barrier resolved ColorAttachment -> Sampled",
+            ]
+        );
     }
 }
@@ -0,0 +1,330 @@
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use crate::QueueType;
+
+use super::{FrameGraph, PassKind};
+
+///frame graph 的结构快照，用于导出 Graphviz DOT 和 JSON
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FrameGraphSnapshot {
+    pub passes: Vec<PassSnapshot>,
+    pub resources: Vec<ResourceSnapshot>,
+    pub resource_nodes: Vec<ResourceNodeSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PassSnapshot {
+    pub index: usize,
+    pub name: String,
+    pub insert_point: usize,
+    pub kind: PassKind,
+    ///compile 之后为实际执行的队列
+    pub queue: QueueType,
+    ///执行顺序，未 compile 时为空
+    pub order: Option<usize>,
+    pub culled: bool,
+    pub side_effect: bool,
+    pub reads: Vec<usize>,
+    pub writes: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResourceSnapshot {
+    pub index: usize,
+    pub name: String,
+    pub imported: bool,
+    ///首次使用此资源的渲染节点
+    pub first_pass: Option<usize>,
+    ///最后使用此资源的渲染节点
+    pub last_pass: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResourceNodeSnapshot {
+    pub index: usize,
+    pub resource: usize,
+    pub version: u32,
+    pub writer: Option<usize>,
+}
+
+impl FrameGraph {
+    pub fn snapshot(&self) -> FrameGraphSnapshot {
+        let compiled = !self.sorted_pass_nodes().is_empty();
+
+        let passes = self
+            .pass_nodes()
+            .iter()
+            .map(|pass_node| PassSnapshot {
+                index: pass_node.handle.index(),
+                name: pass_node.name.clone(),
+                insert_point: pass_node.insert_point,
+                kind: pass_node.kind,
+                queue: pass_node.queue,
+                order: self
+                    .sorted_pass_nodes()
+                    .iter()
+                    .position(|handle| *handle == pass_node.handle),
+                culled: compiled && pass_node.is_culled(),
+                side_effect: pass_node.side_effect,
+                reads: pass_node
+                    .reads
+                    .iter()
+                    .map(|handle| handle.index())
+                    .collect(),
+                writes: pass_node
+                    .writes
+                    .iter()
+                    .map(|handle| handle.index())
+                    .collect(),
+            })
+            .collect();
+
+        let resources = self
+            .resources()
+            .iter()
+            .map(|resource| ResourceSnapshot {
+                index: resource.info.handle.index(),
+                name: resource.info.name.clone(),
+                imported: resource.is_imported(),
+                first_pass: resource
+                    .info
+                    .first_pass_node_handle
+                    .map(|handle| handle.index()),
+                last_pass: resource
+                    .info
+                    .last_pass_node_handle
+                    .map(|handle| handle.index()),
+            })
+            .collect();
+
+        let resource_nodes = self
+            .resource_nodes()
+            .iter()
+            .map(|resource_node| ResourceNodeSnapshot {
+                index: resource_node.handle.index(),
+                resource: resource_node.resource_handle.index(),
+                version: resource_node.version,
+                writer: resource_node
+                    .pass_node_writer_handle
+                    .map(|handle| handle.index()),
+            })
+            .collect();
+
+        FrameGraphSnapshot {
+            passes,
+            resources,
+            resource_nodes,
+        }
+    }
+
+    pub fn export_json(&self) -> String {
+        self.snapshot().to_json()
+    }
+
+    pub fn export_dot(&self) -> String {
+        self.snapshot().to_dot()
+    }
+}
+
+impl FrameGraphSnapshot {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("frame graph snapshot is always serializable")
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+
+        writeln!(dot, "digraph FrameGraph {{").unwrap();
+        writeln!(dot, "    rankdir=LR;").unwrap();
+
+        for pass in self.passes.iter() {
+            let order = pass
+                .order
+                .map(|order| format!("#{order}"))
+                .unwrap_or_else(|| "#-".to_string());
+
+            let style = if pass.culled {
+                ", style=dashed, color=gray"
+            } else if pass.side_effect {
+                ", style=bold"
+            } else {
+                ""
+            };
+
+            let shape = match pass.kind {
+                PassKind::Render => "box",
+                PassKind::Compute => "hexagon",
+                PassKind::Transfer => "cds",
+            };
+
+            writeln!(
+                dot,
+                "    pass_{} [shape={}{}, label=\"{}\\n{} insert {}{}\"];",
+                pass.index,
+                shape,
+                style,
+                escape(&pass.name),
+                order,
+                pass.insert_point,
+                if pass.culled { " (culled)" } else { "" },
+            )
+            .unwrap();
+        }
+
+        for resource_node in self.resource_nodes.iter() {
+            let resource = &self.resources[resource_node.resource];
+
+            let style = if resource.imported {
+                ", style=filled, fillcolor=lightblue"
+            } else {
+                ""
+            };
+
+            writeln!(
+                dot,
+                "    resource_node_{} [shape=ellipse{}, label=\"{} v{}\\nlifetime {}..{}\"];",
+                resource_node.index,
+                style,
+                escape(&resource.name),
+                resource_node.version,
+                self.pass_name(resource.first_pass),
+                self.pass_name(resource.last_pass),
+            )
+            .unwrap();
+        }
+
+        for pass in self.passes.iter() {
+            for read in pass.reads.iter() {
+                writeln!(dot, "    resource_node_{} -> pass_{};", read, pass.index).unwrap();
+            }
+
+            for write in pass.writes.iter() {
+                writeln!(
+                    dot,
+                    "    pass_{} -> resource_node_{} [color=red];",
+                    pass.index, write
+                )
+                .unwrap();
+            }
+        }
+
+        writeln!(dot, "}}").unwrap();
+
+        dot
+    }
+
+    fn pass_name(&self, index: Option<usize>) -> String {
+        index
+            .and_then(|index| self.passes.get(index))
+            .map(|pass| escape(&pass.name))
+            .unwrap_or_else(|| "-".to_string())
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FrameGraph, PassKind};
+
+    use super::super::testing::{add_pass, buffer_desc, texture_desc};
+
+    ///gbuffer -> "lighting"，名字中的引号需要转义，debug 没有读取者会被剔除
+    fn build_graph() -> FrameGraph {
+        let mut fg = FrameGraph::default();
+        let color = fg.create("color", texture_desc(64));
+        let debug = fg.create("debug", buffer_desc());
+
+        let color = add_pass(&mut fg, 0, "gbuffer", PassKind::Render, |builder| {
+            builder.write(color).handle()
+        });
+        add_pass(&mut fg, 1, "debug", PassKind::Compute, |builder| {
+            builder.write(debug);
+        });
+        add_pass(&mut fg, 2, "\"lighting\"", PassKind::Render, |builder| {
+            builder.read(color);
+            builder.side_effect();
+        });
+
+        fg.compile().unwrap();
+        fg
+    }
+
+    #[test]
+    fn json_snapshot() {
+        let json: serde_json::Value = serde_json::from_str(&build_graph().export_json()).unwrap();
+
+        let pass = |index: usize, name: &str, kind: &str, culled, side_effect, reads, writes| {
+            serde_json::json!({
+                "index": index,
+                "name": name,
+                "insert_point": index,
+                "kind": kind,
+                "queue": "graphics",
+                "order": index,
+                "culled": culled,
+                "side_effect": side_effect,
+                "reads": reads,
+                "writes": writes,
+            })
+        };
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "passes": [
+                    pass(0, "gbuffer", "render", false, false, vec![], vec![2]),
+                    pass(1, "debug", "compute", true, false, vec![], vec![3]),
+                    pass(2, "\"lighting\"", "render", false, true, vec![2], vec![]),
+                ],
+                "resources": [
+                    { "index": 0, "name": "color", "imported": false, "first_pass": 0, "last_pass": 2 },
+                    { "index": 1, "name": "debug", "imported": false, "first_pass": null, "last_pass": null },
+                ],
+                "resource_nodes": [
+                    { "index": 0, "resource": 0, "version": 0, "writer": null },
+                    { "index": 1, "resource": 1, "version": 0, "writer": null },
+                    { "index": 2, "resource": 0, "version": 1, "writer": 0 },
+                    { "index": 3, "resource": 1, "version": 1, "writer": 1 },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn dot_snapshot() {
+        let expected = r#"digraph FrameGraph {
+    rankdir=LR;
+    pass_0 [shape=box, label="gbuffer\n#0 insert 0"];
+    pass_1 [shape=hexagon, style=dashed, color=gray, label="debug\n#1 insert 1 (culled)"];
+    pass_2 [shape=box, style=bold, label="\"lighting\"\n#2 insert 2"];
+    resource_node_0 [shape=ellipse, label="color v0\nlifetime gbuffer..\"lighting\""];
+    resource_node_1 [shape=ellipse, label="debug v0\nlifetime -..-"];
+    resource_node_2 [shape=ellipse, label="color v1\nlifetime gbuffer..\"lighting\""];
+    resource_node_3 [shape=ellipse, label="debug v1\nlifetime -..-"];
+    pass_0 -> resource_node_2 [color=red];
+    pass_1 -> resource_node_3 [color=red];
+    resource_node_2 -> pass_2;
+}
+"#;
+
+        assert_eq!(build_graph().export_dot(), expected);
+    }
+
+    #[test]
+    fn uncompiled_graph_has_no_order() {
+        let mut fg = FrameGraph::default();
+        let color = fg.create("color", texture_desc(64));
+        add_pass(&mut fg, 0, "gbuffer", PassKind::Render, |builder| {
+            builder.write(color);
+        });
+
+        let snapshot = fg.snapshot();
+        assert_eq!(snapshot.passes[0].order, None);
+        assert!(!snapshot.passes[0].culled);
+    }
+}
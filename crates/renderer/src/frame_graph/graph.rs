@@ -1,225 +1,2362 @@
-use crate::{Device, gfx_base::TypeHandle};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use cocos_core::tracing::info_span;
+
+use crate::{
+    Buffer, Device, LoadOp, QueueType, ResourceUsage, Texture, TextureSubresourceRange,
+    error::RendererError, gfx_base::TypeHandle,
+};
 
 use super::{
-    CallbackPass, DevicePass, DynPass, FGResource, FGResourceDescriptor, PassNode, PassNodeBuilder,
-    RenderContext, ResourceInfo, ResourceNode, ResourceNodeHandle, ResourceTable,
-    TransientResourceCache, TypeEquals, VirtualResource,
+    AliasingPlan, BarrierInfo, Blackboard, CallbackPass, CompiledPassNode, CompiledPlan,
+    CompiledResource, DevicePass, DynPass, FGResource, FGResourceDescriptor, FrameTimingReport,
+    HistoryResources, PassKind, PassNode, PassNodeBuilder, PlanCache, QueueSync, RecordedCommands,
+    RenderContext, ResourceInfo, ResourceNode, ResourceNodeHandle, ResourceRequest, ResourceTable,
+    TransientMemoryReport, TransientResourceCache, TypeEquals, VirtualResource,
+    VirtualResourceState,
 };
 
+///compute_barriers 记录的纹理范围、使用方式和最后使用它的节点
+type TrackedUsage = (TextureSubresourceRange, ResourceUsage, TypeHandle<PassNode>);
+
+///compile 的可选项，reset 之后保留
+#[derive(Debug, Clone, Default)]
+pub struct CompileOptions {
+    ///将附件相同的相邻渲染节点合并为一个 DevicePass 的多个子通道
+    pub merge_passes: bool,
+    ///设备支持的队列，通常为 Device::queues 的结果。
+    ///为空或者只有图形队列时所有渲染节点都在图形队列上按顺序执行
+    pub queues: Vec<QueueType>,
+}
+
+///execute 录制命令的方式，reset 之后保留
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExecuteMode {
+    ///在调用线程上按顺序录制，结果确定，用于调试
+    #[default]
+    Serial,
+    ///在工作线程上分别录制每个 DevicePass，按执行顺序提交。
+    ///没有读写冲突并且不共享内存的相邻 DevicePass 作为一个批次同时录制，
+    ///DevicePass 只能修改自己写入的资源
+    Parallel {
+        ///为 0 时使用系统的并行度
+        worker_count: usize,
+    },
+}
+
 #[derive(Default)]
 pub struct FrameGraph {
     pass_nodes: Vec<PassNode>,
     resource_nodes: Vec<ResourceNode>,
     resources: Vec<VirtualResource>,
+    ///按依赖关系排序后的渲染节点
+    sorted_pass_nodes: Vec<TypeHandle<PassNode>>,
     device_passes: Option<Vec<DevicePass>>,
+    ///setup 阶段记录的错误，在 compile 时返回
+    setup_errors: Vec<RendererError>,
+    ///临时资源的内存共享方案
+    aliasing_plan: AliasingPlan,
+    compile_options: CompileOptions,
+    execute_mode: ExecuteMode,
+    ///每个元素为合并进同一个 DevicePass 的渲染节点名称
+    merged_passes: Vec<Vec<String>>,
+    ///RenderFlow 之间共享的资源节点
+    blackboard: Blackboard,
+    ///跨帧保留的历史资源，reset 之后保留
+    history: HistoryResources,
+    ///上一帧的编译结果，reset 之后保留
+    plan_cache: PlanCache,
+    ///句柄的代数，用于发现 reset 之前保留下来的句柄
+    epoch: u32,
+    ///本帧实例化的模块名称
+    module_instances: HashSet<String>,
+    ///最近一次 execute 中每个渲染节点的执行时间，reset 之后保留
+    timing_report: FrameTimingReport,
 }
 
 impl FrameGraph {
     pub fn reset(&mut self) {
-        *self = FrameGraph::default();
+        let compile_options = std::mem::take(&mut self.compile_options);
+        let mut history = std::mem::take(&mut self.history);
+        history.end_frame();
+        let plan_cache = std::mem::take(&mut self.plan_cache);
+        let timing_report = std::mem::take(&mut self.timing_report);
+
+        *self = FrameGraph {
+            compile_options,
+            execute_mode: self.execute_mode,
+            history,
+            plan_cache,
+            timing_report,
+            epoch: self.epoch.wrapping_add(1),
+            ..Default::default()
+        };
+    }
+
+    pub fn set_compile_options(&mut self, compile_options: CompileOptions) {
+        self.compile_options = compile_options;
+    }
+
+    pub fn compile_options(&self) -> &CompileOptions {
+        &self.compile_options
+    }
+
+    pub fn set_execute_mode(&mut self, execute_mode: ExecuteMode) {
+        self.execute_mode = execute_mode;
+    }
+
+    pub fn execute_mode(&self) -> ExecuteMode {
+        self.execute_mode
+    }
+
+    pub fn history(&self) -> &HistoryResources {
+        &self.history
+    }
+
+    pub fn history_mut(&mut self) -> &mut HistoryResources {
+        &mut self.history
+    }
+
+    pub fn plan_cache(&self) -> &PlanCache {
+        &self.plan_cache
+    }
+
+    pub fn plan_cache_mut(&mut self) -> &mut PlanCache {
+        &mut self.plan_cache
+    }
+
+    pub fn timing_report(&self) -> &FrameTimingReport {
+        &self.timing_report
+    }
+
+    pub fn blackboard(&self) -> &Blackboard {
+        &self.blackboard
+    }
+
+    pub fn blackboard_mut(&mut self) -> &mut Blackboard {
+        &mut self.blackboard
     }
 
+    ///最近一次 compile 中被合并的渲染节点，未开启 merge_passes 时为空
+    pub fn merged_passes(&self) -> &[Vec<String>] {
+        &self.merged_passes
+    }
+
+    ///某个渲染节点出错时不再提交，本帧申请的资源仍然归还给缓存，历史纹理按没有执行处理
     pub fn execute(
         &mut self,
         device: &Device,
         transient_resource_cache: &mut TransientResourceCache,
-    ) {
-        let mut render_context = RenderContext::new(device, transient_resource_cache);
+    ) -> Result<(), RendererError> {
+        let device_passes = self
+            .device_passes
+            .take()
+            .ok_or(RendererError::NotCompiled)?;
 
-        let device_passes = self.device_passes.take().unwrap();
+        let _span = info_span!("frame_graph_execute").entered();
 
-        for mut device_pass in device_passes {
-            device_pass.execute(&mut render_context);
-        }
+        let (mut resource_table, result) = match self.execute_mode {
+            ExecuteMode::Serial => {
+                self.execute_serial(device_passes, device, transient_resource_cache)
+            }
+            ExecuteMode::Parallel { worker_count } => self.execute_parallel(
+                device_passes,
+                device,
+                transient_resource_cache,
+                worker_count,
+            ),
+        };
+
+        let result = result.map(|timing_report| {
+            self.timing_report = timing_report;
+            self.history.update(&self.resources, &mut resource_table);
+        });
+        resource_table.release_all(transient_resource_cache);
+
+        transient_resource_cache.end_frame();
+
+        result
     }
 
-    pub fn compile(
-        &mut self,
+    fn execute_serial(
+        &self,
+        device_passes: Vec<DevicePass>,
         device: &Device,
         transient_resource_cache: &mut TransientResourceCache,
-    ) {
-        if self.pass_nodes.is_empty() {
-            return;
-        }
+    ) -> (ResourceTable, Result<FrameTimingReport, RendererError>) {
+        let mut render_context =
+            RenderContext::new(device, &self.resources, transient_resource_cache);
 
-        self.sort();
-        //todo cull
+        let result = device_passes
+            .into_iter()
+            .try_for_each(|mut device_pass| device_pass.execute(&mut render_context));
 
-        self.compute_resource_lifetime();
+        if result.is_ok() {
+            render_context.submit();
+        }
 
-        // self.compiled_pipelines(pipeline_cache);
+        let timing_report = render_context.take_timing_report();
 
-        self.generate_device_passes(device, transient_resource_cache);
+        (
+            render_context.take_resource_table(),
+            result.map(|()| timing_report),
+        )
     }
 
-    fn generate_device_passes(
-        &mut self,
+    ///按批次录制，同一批次中的 DevicePass 在工作线程上同时录制。批次开始之前申请资源，
+    ///结束之后释放最后一次使用的资源，之后的批次可以复用共享内存。出错时返回第一个错误和已经申请的资源
+    fn execute_parallel(
+        &self,
+        device_passes: Vec<DevicePass>,
         device: &Device,
         transient_resource_cache: &mut TransientResourceCache,
-    ) {
-        let mut device_passes = vec![];
+        worker_count: usize,
+    ) -> (ResourceTable, Result<FrameTimingReport, RendererError>) {
+        let worker_count = match worker_count {
+            0 => std::thread::available_parallelism().map_or(1, |count| count.get()),
+            worker_count => worker_count,
+        }
+        .max(1);
 
-        for index in 0..self.pass_nodes.len() {
-            let pass_node_handle = self.pass_nodes[index].handle;
+        let mut resource_table = ResourceTable::default();
+        let mut recorded = vec![];
 
-            let mut resource_table: ResourceTable = ResourceTable::default();
+        for wave in self.parallel_waves(device_passes) {
+            for device_pass in wave.iter() {
+                for request in device_pass.resource_requests() {
+                    resource_table.request_resources(request, device, transient_resource_cache);
+                }
+            }
 
-            for resource_handle in self
-                .get_pass_node(&pass_node_handle)
-                .resource_request_array
-                .clone()
-            {
-                let resource = self.get_resource(&resource_handle);
-                resource_table.request_resources(resource, device, transient_resource_cache);
+            //DevicePass 写入的资源由录制的线程独占
+            let wave = wave
+                .into_iter()
+                .map(|device_pass| {
+                    let mut owned = ResourceTable::default();
+
+                    for handle in device_pass.write_handles() {
+                        if let Some(resource) = resource_table.take_resource(handle) {
+                            owned.insert_resource(*handle, resource);
+                        }
+                    }
+
+                    (device_pass, owned)
+                })
+                .collect::<Vec<_>>();
+
+            let results = self.record_wave(wave, device, &resource_table, worker_count);
+
+            let mut error = None;
+            for (device_pass, result, owned) in results {
+                resource_table.append(owned);
+
+                for handle in device_pass.resource_releases() {
+                    resource_table.release_resource(handle, transient_resource_cache);
+                }
+
+                match result {
+                    Ok(commands) => recorded.push(commands),
+                    Err(e) => {
+                        error.get_or_insert(e);
+                    }
+                }
+            }
+
+            //返回执行顺序中第一个错误
+            if let Some(error) = error {
+                return (resource_table, Err(error));
             }
+        }
 
-            let mut device_pass = DevicePass::new(resource_table);
+        let mut render_context = RenderContext::shared(
+            device,
+            &self.resources,
+            ResourceTable::default(),
+            &resource_table,
+        );
+        for commands in recorded {
+            render_context.append_recorded(commands);
+        }
 
-            device_pass.extra(self, pass_node_handle);
+        render_context.submit();
+        let timing_report = render_context.take_timing_report();
 
-            device_passes.push(device_pass);
+        (resource_table, Ok(timing_report))
+    }
+
+    ///按执行顺序把 DevicePass 分为可以同时录制的批次。DevicePass 写入同一批次中其他 DevicePass
+    ///读写的资源，或者使用的资源与同一批次中的其他资源共享内存时开始新的批次
+    fn parallel_waves(&self, device_passes: Vec<DevicePass>) -> Vec<Vec<DevicePass>> {
+        let mut waves: Vec<Vec<DevicePass>> = vec![];
+        let mut accessed: HashSet<TypeHandle<VirtualResource>> = HashSet::default();
+        let mut written: HashSet<TypeHandle<VirtualResource>> = HashSet::default();
+        let mut slots: HashMap<usize, TypeHandle<VirtualResource>> = HashMap::default();
+
+        for device_pass in device_passes {
+            let writes_accessed = device_pass
+                .write_handles()
+                .any(|handle| accessed.contains(handle));
+            let accesses_written = device_pass
+                .resource_handles()
+                .any(|handle| written.contains(handle));
+            let shares_slot = device_pass.resource_handles().any(|handle| {
+                self.aliasing_plan
+                    .get_slot_index(handle)
+                    .and_then(|slot_index| slots.get(&slot_index))
+                    .is_some_and(|other| other != handle)
+            });
+
+            if waves.is_empty() || writes_accessed || accesses_written || shares_slot {
+                waves.push(vec![]);
+                accessed.clear();
+                written.clear();
+                slots.clear();
+            }
+
+            accessed.extend(device_pass.resource_handles().copied());
+            written.extend(device_pass.write_handles().copied());
+            for handle in device_pass.resource_handles() {
+                if let Some(slot_index) = self.aliasing_plan.get_slot_index(handle) {
+                    slots.insert(slot_index, *handle);
+                }
+            }
+
+            waves.last_mut().unwrap().push(device_pass);
         }
 
-        self.device_passes = Some(device_passes);
+        waves
     }
 
-    fn sort(&mut self) {
-        self.pass_nodes
-            .sort_by(|a, b| a.insert_point.cmp(&b.insert_point));
+    ///在工作线程上录制一个批次，按执行顺序返回录制结果和独占的资源
+    fn record_wave(
+        &self,
+        wave: Vec<(DevicePass, ResourceTable)>,
+        device: &Device,
+        shared: &ResourceTable,
+        worker_count: usize,
+    ) -> Vec<(
+        DevicePass,
+        Result<RecordedCommands, RendererError>,
+        ResourceTable,
+    )> {
+        let worker_count = worker_count.min(wave.len());
+        let resources = &self.resources;
+        let mut results = (0..wave.len()).map(|_| None).collect::<Vec<_>>();
+        let queue = Mutex::new(wave.into_iter().enumerate());
+        let results_lock = Mutex::new(&mut results);
+
+        std::thread::scope(|scope| {
+            for _ in 0..worker_count {
+                scope.spawn(|| {
+                    loop {
+                        let Some((index, (mut device_pass, owned))) = queue.lock().unwrap().next()
+                        else {
+                            break;
+                        };
+
+                        let mut render_context =
+                            RenderContext::shared(device, resources, owned, shared);
+                        let result = device_pass
+                            .execute(&mut render_context)
+                            .map(|_| render_context.take_recorded());
+                        let owned = render_context.take_resource_table();
+
+                        results_lock.lock().unwrap()[index] = Some((device_pass, result, owned));
+                    }
+                });
+            }
+        });
+
+        results.into_iter().flatten().collect()
     }
 
-    fn compute_resource_lifetime(&mut self) {
-        for pass_node in self.pass_nodes.iter_mut() {
-            //更新渲染节点读取的资源节点所指向资源的生命周期
-            for resource_node_handle in pass_node.reads.iter() {
-                let resource_node = &self.resource_nodes[resource_node_handle.index()];
-                let resource = &mut self.resources[resource_node.resource_handle.index()];
-                resource.info.update_lifetime(pass_node.handle);
+    pub fn compile(&mut self) -> Result<(), RendererError> {
+        if self.pass_nodes.is_empty() {
+            self.device_passes = Some(vec![]);
+            return Ok(());
+        }
+
+        self.validate()?;
+
+        //历史纹理按本帧的使用方式重新创建之后才能确定结构
+        self.resolve_usages();
+        self.prepare_history_textures()?;
+
+        //结构与上一帧相同时跳过排序、剔除和内存共享的计算
+        let key = self.structure_hash();
+        let (groups, queue_syncs) = match self.plan_cache.take(key, self.plan_shape()) {
+            Some(plan) => {
+                self.apply_compiled_plan(&plan);
+
+                let groups = plan.groups.clone();
+                let queue_syncs = plan.queue_syncs.clone();
+                self.plan_cache.insert(key, plan);
+                (groups, queue_syncs)
             }
+            None => {
+                self.sort()?;
+                self.cull();
+                self.assign_queues();
 
-            //更新渲染节点吸入的资源节点所指向资源的生命周期
-            for resource_node_handle in pass_node.writes.iter() {
-                let resource_node = &self.resource_nodes[resource_node_handle.index()];
-                let resource = &mut self.resources[resource_node.resource_handle.index()];
-                resource.info.update_lifetime(pass_node.handle);
+                self.accumulate_usages();
+                self.compute_barriers();
+
+                self.compute_resource_lifetime();
+
+                let groups = self.merge_groups();
+                self.aliasing_plan = AliasingPlan::new(&groups, &self.resources);
+
+                let queue_syncs = self.compute_queue_syncs(&groups);
+                let plan = self.compiled_plan(groups.clone(), queue_syncs.clone());
+                self.plan_cache.insert(key, plan);
+                (groups, queue_syncs)
             }
-        }
+        };
 
-        //更新pass_node中资源使用的索引顺序
-        for resource_index in 0..self.resources.len() {
-            let resource = &self.resources[resource_index];
-            let info = resource.info.clone();
+        // self.compiled_pipelines(pipeline_cache);
 
-            if info.first_pass_node_handle.is_none() || info.last_pass_node_handle.is_none() {
+        self.generate_device_passes(&groups, &queue_syncs);
+
+        Ok(())
+    }
+
+    ///按执行顺序把未剔除的渲染节点分组，每组生成一个 DevicePass
+    fn merge_groups(&self) -> Vec<Vec<TypeHandle<PassNode>>> {
+        let mut groups: Vec<Vec<TypeHandle<PassNode>>> = vec![];
+
+        for pass_node_handle in self.sorted_pass_nodes.iter() {
+            if self.pass_node(pass_node_handle).is_culled() {
                 continue;
             }
 
-            let first_pass_node_handle = info.first_pass_node_handle.unwrap();
-            let first_pass_node = &mut self.pass_nodes[first_pass_node_handle.index()];
-            first_pass_node.resource_request_array.push(info.handle);
+            let merge = self.compile_options.merge_passes
+                && groups
+                    .last()
+                    .is_some_and(|group| self.can_merge(group, pass_node_handle));
 
-            let last_pass_node_handle = info.last_pass_node_handle.unwrap();
-            let last_pass_node = &mut self.pass_nodes[last_pass_node_handle.index()];
-            last_pass_node.resource_release_array.push(info.handle);
+            if merge {
+                groups.last_mut().unwrap().push(*pass_node_handle);
+            } else {
+                groups.push(vec![*pass_node_handle]);
+            }
         }
+
+        groups
     }
-}
 
-impl FrameGraph {
-    pub fn create<DescriptorType>(&mut self, name: &str, desc: DescriptorType) -> ResourceNodeHandle<DescriptorType::Resource>
-    where
-        DescriptorType: FGResourceDescriptor + TypeEquals<Other = <<DescriptorType as FGResourceDescriptor>::Resource as FGResource>::Descriptor>,
-    {
-        let resource_handle = TypeHandle::new(self.resources.len());
+    fn compiled_plan(
+        &self,
+        groups: Vec<Vec<TypeHandle<PassNode>>>,
+        queue_syncs: Vec<QueueSync>,
+    ) -> CompiledPlan {
+        CompiledPlan {
+            shape: self.plan_shape(),
+            sorted_pass_nodes: self.sorted_pass_nodes.clone(),
+            pass_nodes: self
+                .pass_nodes
+                .iter()
+                .map(|pass_node| CompiledPassNode {
+                    ref_count: pass_node.ref_count,
+                    side_effect: pass_node.side_effect,
+                    queue: pass_node.queue,
+                    usages: pass_node.usages.clone(),
+                    barriers: pass_node.barriers.clone(),
+                    resource_request_array: pass_node.resource_request_array.clone(),
+                    resource_release_array: pass_node.resource_release_array.clone(),
+                })
+                .collect(),
+            resources: self
+                .resources
+                .iter()
+                .map(|resource| CompiledResource {
+                    desc: match &resource.state {
+                        VirtualResourceState::Setup(desc) => Some(desc.clone()),
+                        VirtualResourceState::Imported(_) => None,
+                    },
+                    first_pass_node_handle: resource.info.first_pass_node_handle,
+                    last_pass_node_handle: resource.info.last_pass_node_handle,
+                    cross_queue: resource.cross_queue,
+                })
+                .collect(),
+            reader_counts: self
+                .resource_nodes
+                .iter()
+                .map(|resource_node| resource_node.reader_count)
+                .collect(),
+            aliasing_plan: self.aliasing_plan.clone(),
+            groups,
+            queue_syncs,
+        }
+    }
 
-        let resource: VirtualResource = VirtualResource::setup::<DescriptorType::Resource>(
-            name,
-            resource_handle,
-            TypeEquals::same(desc),
-        );
+    fn apply_compiled_plan(&mut self, plan: &CompiledPlan) {
+        self.sorted_pass_nodes = plan.sorted_pass_nodes.clone();
+        self.aliasing_plan = plan.aliasing_plan.clone();
 
-        let resource_info = resource.info.clone();
-        self.resources.push(resource);
+        for (pass_node, compiled) in self.pass_nodes.iter_mut().zip(plan.pass_nodes.iter()) {
+            pass_node.ref_count = compiled.ref_count;
+            pass_node.side_effect = compiled.side_effect;
+            pass_node.queue = compiled.queue;
+            pass_node.usages = compiled.usages.clone();
+            pass_node.barriers = compiled.barriers.clone();
+            pass_node.resource_request_array = compiled.resource_request_array.clone();
+            pass_node.resource_release_array = compiled.resource_release_array.clone();
+        }
 
-        let handle = self.create_resource_node(resource_info);
+        for (resource, compiled) in self.resources.iter_mut().zip(plan.resources.iter()) {
+            if let (VirtualResourceState::Setup(desc), Some(compiled_desc)) =
+                (&mut resource.state, &compiled.desc)
+            {
+                *desc = compiled_desc.clone();
+            }
 
-        ResourceNodeHandle::new(handle, resource_handle)
+            resource.info.first_pass_node_handle = compiled.first_pass_node_handle;
+            resource.info.last_pass_node_handle = compiled.last_pass_node_handle;
+            resource.cross_queue = compiled.cross_queue;
+        }
+
+        for (resource_node, reader_count) in self
+            .resource_nodes
+            .iter_mut()
+            .zip(plan.reader_counts.iter())
+        {
+            resource_node.reader_count = *reader_count;
+        }
     }
 
-    pub fn create_resource_node(
+    fn generate_device_passes(
         &mut self,
-        resource_info: ResourceInfo,
-    ) -> TypeHandle<ResourceNode> {
-        let resource_handle = resource_info.handle;
-        let version = resource_info.version();
+        groups: &[Vec<TypeHandle<PassNode>>],
+        queue_syncs: &[QueueSync],
+    ) {
+        let mut device_passes: Vec<DevicePass> = vec![];
+        let mut timestamp_count = 0;
 
-        let handle = TypeHandle::new(self.resource_nodes.len());
+        for (group, queue_sync) in groups.iter().zip(queue_syncs.iter()) {
+            //并行录制时每个 DevicePass 的时间戳查询在 compile 时确定
+            let mut device_pass = DevicePass::new(queue_sync.clone(), timestamp_count);
 
-        self.resource_nodes
-            .push(ResourceNode::new(handle, resource_handle, version));
+            for pass_node_handle in group.iter() {
+                device_pass.extra(self, *pass_node_handle);
+            }
 
-        handle
-    }
+            timestamp_count += device_pass.timestamp_count();
 
-    pub fn add_callback_pass<Data, Setup, Execute>(
-        &mut self,
-        insert_point: usize,
-        name: &str,
-        setup: Setup,
-        execute: Execute,
-    ) where
-        Data: Default + 'static,
-        Setup: FnOnce(&mut PassNodeBuilder, &mut Data) + 'static,
-        Execute: FnOnce(&Data, &mut RenderContext) + 'static,
-    {
-        let pass = CallbackPass::new(setup, execute);
+            device_passes.push(device_pass);
+        }
+
+        self.merged_passes = groups
+            .iter()
+            .filter(|group| group.len() > 1)
+            .map(|group| {
+                group
+                    .iter()
+                    .map(|handle| self.pass_node(handle).name.clone())
+                    .collect()
+            })
+            .collect();
 
-        self.add_pass(insert_point, name, Box::new(pass));
+        self.device_passes = Some(device_passes);
     }
 
-    pub fn add_pass(&mut self, insert_point: usize, name: &str, mut pass: DynPass) {
-        let handle = self.get_current_pass_node_handle();
-        let mut builder = PassNodeBuilder::new(insert_point, name, handle, self);
+    ///附件完全相同、不清除附件，并且只在同一像素读取当前 DevicePass 输出的渲染节点可以作为子通道合并
+    fn can_merge(&self, group: &[TypeHandle<PassNode>], handle: &TypeHandle<PassNode>) -> bool {
+        let first = self.pass_node(&group[0]);
+        let pass_node = self.pass_node(handle);
 
-        pass.setup(&mut builder);
+        if first.kind != PassKind::Render || pass_node.kind != PassKind::Render {
+            return false;
+        }
 
-        let mut pass_node = builder.build();
-        pass_node.pass = Some(pass);
+        let attachments = pass_node.attachment_handles();
+        if attachments.is_empty()
+            || attachments != first.attachment_handles()
+            || pass_node.attachment_ranges() != first.attachment_ranges()
+        {
+            return false;
+        }
 
-        self.pass_nodes.push(pass_node);
-    }
+        let clears_color = pass_node
+            .color_attachments
+            .iter()
+            .any(|attachment| attachment.ops.load != LoadOp::Load);
+        let clears_depth_stencil =
+            pass_node
+                .depth_stencil_attachment
+                .as_ref()
+                .is_some_and(|attachment| {
+                    attachment
+                        .depth_ops
+                        .is_some_and(|ops| ops.load != LoadOp::Load)
+                        || attachment
+                            .stencil_ops
+                            .is_some_and(|ops| ops.load != LoadOp::Load)
+                });
+        if clears_color || clears_depth_stencil {
+            return false;
+        }
 
-    pub fn get_current_pass_node_handle(&self) -> TypeHandle<PassNode> {
-        TypeHandle::new(self.pass_nodes.len())
-    }
+        //读取当前 DevicePass 的输出时只能作为输入附件读取
+        let reads_attachments_only = pass_node.reads.iter().all(|resource_node_handle| {
+            let resource_node = self.resource_node(resource_node_handle);
 
-    pub fn get_pass_node_mut(&mut self, handle: &TypeHandle<PassNode>) -> &mut PassNode {
-        &mut self.pass_nodes[handle.index()]
-    }
+            match resource_node.pass_node_writer_handle {
+                Some(writer) if group.contains(&writer) => {
+                    attachments.contains(&resource_node.resource_handle)
+                }
+                _ => true,
+            }
+        });
 
-    pub fn get_pass_node(&self, handle: &TypeHandle<PassNode>) -> &PassNode {
-        &self.pass_nodes[handle.index()]
+        //子通道的屏障在渲染通道开始之前录制，不能依赖前面子通道写入的其他资源
+        let writes_group_resources = pass_node.writes.iter().any(|resource_node_handle| {
+            let resource_handle = self.resource_node(resource_node_handle).resource_handle;
+
+            !attachments.contains(&resource_handle)
+                && self.resource_nodes.iter().any(|resource_node| {
+                    resource_node.resource_handle == resource_handle
+                        && resource_node
+                            .pass_node_writer_handle
+                            .is_some_and(|writer| group.contains(&writer))
+                })
+        });
+
+        reads_attachments_only && !writes_group_resources
     }
 
-    pub fn get_resource_node(&self, handle: &TypeHandle<ResourceNode>) -> &ResourceNode {
-        &self.resource_nodes[handle.index()]
+    ///临时资源使用共享内存的描述申请，保证共享同一块内存的资源可以复用释放的资源
+    pub(crate) fn create_resource_request(
+        &self,
+        handle: &TypeHandle<VirtualResource>,
+    ) -> ResourceRequest {
+        let resource = self.resource(handle);
+
+        let state = match (&resource.state, self.aliasing_plan.get_slot(handle)) {
+            (VirtualResourceState::Setup(_), Some(slot)) => {
+                VirtualResourceState::Setup(slot.desc.clone())
+            }
+            (state, _) => state.clone(),
+        };
+
+        ResourceRequest {
+            handle: *handle,
+            name: resource.info.name.clone(),
+            state,
+        }
     }
 
-    pub fn get_resource_node_mut(
-        &mut self,
-        handle: &TypeHandle<ResourceNode>,
-    ) -> &mut ResourceNode {
-        &mut self.resource_nodes[handle.index()]
+    fn validate(&mut self) -> Result<(), RendererError> {
+        if !self.setup_errors.is_empty() {
+            return Err(self.setup_errors.remove(0));
+        }
+
+        for pass_node in self.pass_nodes.iter() {
+            if pass_node.kind != PassKind::Render && !pass_node.attachment_handles().is_empty() {
+                return Err(RendererError::AttachmentOutsideRenderPass {
+                    pass: pass_node.name.clone(),
+                });
+            }
+
+            if !pass_node.kind.supports_queue(pass_node.queue) {
+                return Err(RendererError::UnsupportedQueue {
+                    pass: pass_node.name.clone(),
+                    kind: pass_node.kind,
+                    queue: pass_node.queue,
+                });
+            }
+
+            for resource_node_handle in pass_node.reads.iter() {
+                let resource_node = self.resource_node(resource_node_handle);
+                let resource = self.resource(&resource_node.resource_handle);
+
+                if resource_node.pass_node_writer_handle.is_none() && !resource.is_imported() {
+                    return Err(RendererError::ReadUnwrittenResource {
+                        pass: pass_node.name.clone(),
+                        resource: resource.info.name.clone(),
+                    });
+                }
+            }
+        }
+
+        let mut writers: HashMap<TypeHandle<ResourceNode>, TypeHandle<PassNode>> =
+            HashMap::default();
+
+        for resource_node in self.resource_nodes.iter() {
+            let (Some(source_handle), Some(writer_handle)) = (
+                resource_node.source_handle,
+                resource_node.pass_node_writer_handle,
+            ) else {
+                continue;
+            };
+
+            if let Some(first_writer_handle) = writers.insert(source_handle, writer_handle) {
+                let source = self.resource_node(&source_handle);
+
+                return Err(RendererError::DuplicateWrite {
+                    resource: self.resource(&source.resource_handle).info.name.clone(),
+                    version: source.version,
+                    first_pass: self.pass_node(&first_writer_handle).name.clone(),
+                    second_pass: self.pass_node(&writer_handle).name.clone(),
+                });
+            }
+        }
+
+        Ok(())
     }
 
-    pub fn get_resource(&self, handle: &TypeHandle<VirtualResource>) -> &VirtualResource {
-        &self.resources[handle.index()]
+    ///根据资源的读写关系建立渲染节点的依赖图，并进行稳定的拓扑排序，insert_point 仅用于决定无依赖节点的先后
+    fn sort(&mut self) -> Result<(), RendererError> {
+        let pass_count = self.pass_nodes.len();
+        let mut successors: Vec<Vec<usize>> = vec![vec![]; pass_count];
+        let mut in_degrees = vec![0usize; pass_count];
+
+        let mut add_edge = |from: usize, to: usize| {
+            if from != to && !successors[from].contains(&to) {
+                successors[from].push(to);
+                in_degrees[to] += 1;
+            }
+        };
+
+        let mut readers: Vec<Vec<(usize, TextureSubresourceRange)>> =
+            vec![vec![]; self.resource_nodes.len()];
+        for pass_node in self.pass_nodes.iter() {
+            for resource_node_handle in pass_node.reads.iter() {
+                readers[resource_node_handle.index()].push((
+                    pass_node.handle.index(),
+                    pass_node.read_range(resource_node_handle),
+                ));
+            }
+        }
+
+        //只有纹理范围重叠的读写之间才建立依赖
+        for resource_node in self.resource_nodes.iter() {
+            let versions = self.resource_nodes.iter().filter(|other| {
+                other.resource_handle == resource_node.resource_handle
+                    && other.version <= resource_node.version
+            });
+
+            //读取者依赖于写入过读取范围的渲染节点
+            for &(reader, range) in readers[resource_node.handle.index()].iter() {
+                if let Some(writer_handle) = resource_node.pass_node_writer_handle
+                    && reader < writer_handle.index()
+                {
+                    return Err(RendererError::ReadBeforeWrite {
+                        pass: self.pass_nodes[reader].name.clone(),
+                        resource: self.resources[resource_node.resource_handle.index()]
+                            .info
+                            .name
+                            .clone(),
+                        version: resource_node.version,
+                        writer: self.pass_nodes[writer_handle.index()].name.clone(),
+                    });
+                }
+
+                for version in versions.clone() {
+                    if let Some(writer_handle) = version.pass_node_writer_handle
+                        && version.range.overlaps(&range)
+                    {
+                        add_edge(writer_handle.index(), reader);
+                    }
+                }
+            }
+
+            let Some(writer_handle) = resource_node.pass_node_writer_handle else {
+                continue;
+            };
+            let writer = writer_handle.index();
+
+            //写入新版本之前，旧版本重叠范围的读取者和写入者必须已经执行
+            for previous in versions.filter(|previous| previous.version < resource_node.version) {
+                for &(reader, range) in readers[previous.handle.index()].iter() {
+                    if range.overlaps(&resource_node.range) {
+                        add_edge(reader, writer);
+                    }
+                }
+
+                if let Some(previous_writer) = previous.pass_node_writer_handle
+                    && previous.range.overlaps(&resource_node.range)
+                {
+                    add_edge(previous_writer.index(), writer);
+                }
+            }
+        }
+
+        let mut ready = BinaryHeap::new();
+        for (index, pass_node) in self.pass_nodes.iter().enumerate() {
+            if in_degrees[index] == 0 {
+                ready.push(Reverse((pass_node.insert_point, index)));
+            }
+        }
+
+        let mut sorted_pass_nodes = Vec::with_capacity(pass_count);
+        while let Some(Reverse((_, index))) = ready.pop() {
+            sorted_pass_nodes.push(self.pass_nodes[index].handle);
+
+            for &successor in successors[index].iter() {
+                in_degrees[successor] -= 1;
+
+                if in_degrees[successor] == 0 {
+                    ready.push(Reverse((
+                        self.pass_nodes[successor].insert_point,
+                        successor,
+                    )));
+                }
+            }
+        }
+
+        if sorted_pass_nodes.len() != pass_count {
+            let passes = self
+                .pass_nodes
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| in_degrees[*index] > 0)
+                .map(|(_, pass_node)| pass_node.name.clone())
+                .collect();
+
+            return Err(RendererError::DependencyCycle { passes });
+        }
+
+        self.sorted_pass_nodes = sorted_pass_nodes;
+
+        Ok(())
     }
 
-    pub fn get_resource_mut(
-        &mut self,
-        handle: &TypeHandle<VirtualResource>,
-    ) -> &mut VirtualResource {
-        &mut self.resources[handle.index()]
+    fn cull(&mut self) {
+        for resource_node in self.resource_nodes.iter_mut() {
+            resource_node.reader_count = 0;
+        }
+
+        for pass_node in self.pass_nodes.iter_mut() {
+            pass_node.ref_count = pass_node.writes.len() as u32;
+
+            for resource_node_handle in pass_node.reads.iter() {
+                self.resource_nodes[resource_node_handle.index()].reader_count += 1;
+            }
+
+            //只写入部分范围时其余范围的内容来自旧版本，旧版本视为被读取
+            for resource_node_handle in pass_node.writes.iter() {
+                if let Some(source_handle) =
+                    Self::preserved_source(&self.resource_nodes, resource_node_handle)
+                {
+                    self.resource_nodes[source_handle.index()].reader_count += 1;
+                }
+            }
+
+            //写入导入资源或跨帧资源的渲染节点视为具有副作用
+            let writes_imported = pass_node.writes.iter().any(|resource_node_handle| {
+                let resource_node = &self.resource_nodes[resource_node_handle.index()];
+                let resource = &self.resources[resource_node.resource_handle.index()];
+
+                resource.is_imported() || resource.persistent
+            });
+
+            if writes_imported {
+                pass_node.side_effect = true;
+            }
+        }
+
+        //从没有被读取的资源节点开始，逐层减少写入节点的引用计数
+        let mut stack = self
+            .resource_nodes
+            .iter()
+            .filter(|resource_node| {
+                resource_node.reader_count == 0 && resource_node.pass_node_writer_handle.is_some()
+            })
+            .map(|resource_node| resource_node.handle)
+            .collect::<Vec<_>>();
+
+        while let Some(resource_node_handle) = stack.pop() {
+            let Some(pass_node_writer_handle) =
+                self.resource_nodes[resource_node_handle.index()].pass_node_writer_handle
+            else {
+                continue;
+            };
+
+            let pass_node = &mut self.pass_nodes[pass_node_writer_handle.index()];
+            pass_node.ref_count -= 1;
+
+            if !pass_node.is_culled() {
+                continue;
+            }
+
+            let preserved = pass_node.writes.iter().filter_map(|write_handle| {
+                Self::preserved_source(&self.resource_nodes, write_handle)
+            });
+            let read_handles = pass_node
+                .reads
+                .iter()
+                .copied()
+                .chain(preserved)
+                .collect::<Vec<_>>();
+
+            for read_handle in read_handles {
+                let resource_node = &mut self.resource_nodes[read_handle.index()];
+                resource_node.reader_count -= 1;
+
+                if resource_node.reader_count == 0 {
+                    stack.push(read_handle);
+                }
+            }
+        }
+    }
+
+    ///设备不支持的队列回退到图形队列，标记异步队列上的渲染节点使用的资源
+    fn assign_queues(&mut self) {
+        for pass_node in self.pass_nodes.iter_mut() {
+            if !self.compile_options.queues.contains(&pass_node.queue) {
+                pass_node.queue = QueueType::Graphics;
+            }
+
+            if pass_node.is_culled() || pass_node.queue == QueueType::Graphics {
+                continue;
+            }
+
+            for resource_node_handle in pass_node.reads.iter().chain(pass_node.writes.iter()) {
+                let resource_handle =
+                    self.resource_nodes[resource_node_handle.index()].resource_handle;
+                self.resources[resource_handle.index()].cross_queue = true;
+            }
+        }
+    }
+
+    fn preserved_source(
+        resource_nodes: &[ResourceNode],
+        resource_node_handle: &TypeHandle<ResourceNode>,
+    ) -> Option<TypeHandle<ResourceNode>> {
+        let resource_node = &resource_nodes[resource_node_handle.index()];
+
+        if resource_node.range.is_all() {
+            None
+        } else {
+            resource_node.source_handle
+        }
+    }
+
+    ///推导 setup 时没有指定使用方式的读写
+    fn resolve_usages(&mut self) {
+        for pass_node in self.pass_nodes.iter_mut() {
+            let accesses = pass_node
+                .reads
+                .iter()
+                .map(|handle| (*handle, false))
+                .chain(pass_node.writes.iter().map(|handle| (*handle, true)))
+                .collect::<Vec<_>>();
+
+            for (resource_node_handle, is_write) in accesses {
+                if pass_node.usages.contains_key(&resource_node_handle) {
+                    continue;
+                }
+
+                let resource_node = &self.resource_nodes[resource_node_handle.index()];
+                let resource_handle = resource_node.resource_handle;
+                let is_texture = self.resources[resource_handle.index()]
+                    .texture_desc()
+                    .is_some();
+                let range = if is_write {
+                    resource_node.range
+                } else {
+                    pass_node.read_range(&resource_node_handle)
+                };
+
+                let usage =
+                    Self::infer_usage(pass_node, resource_handle, range, is_texture, is_write);
+                pass_node.usages.insert(resource_node_handle, usage);
+            }
+        }
+    }
+
+    ///同一个渲染节点读取自己的附件时是附件的加载，不是采样
+    fn infer_usage(
+        pass_node: &PassNode,
+        resource_handle: TypeHandle<VirtualResource>,
+        range: TextureSubresourceRange,
+        is_texture: bool,
+        is_write: bool,
+    ) -> ResourceUsage {
+        let is_color_attachment = pass_node.color_attachments.iter().any(|attachment| {
+            (attachment.texture == resource_handle && attachment.range.overlaps(&range))
+                || attachment.resolve_target == Some(resource_handle)
+        });
+        let depth_stencil_attachment =
+            pass_node
+                .depth_stencil_attachment
+                .as_ref()
+                .filter(|attachment| {
+                    attachment.texture == resource_handle && attachment.range.overlaps(&range)
+                });
+        let is_depth_stencil_attachment = depth_stencil_attachment.is_some();
+        //可写的深度模板附件被读取时是写入之前的加载
+        let is_writable_depth_stencil_attachment =
+            depth_stencil_attachment.is_some_and(|attachment| {
+                attachment.depth_ops.is_some() || attachment.stencil_ops.is_some()
+            });
+
+        match (pass_node.kind, is_write) {
+            (PassKind::Transfer, false) => ResourceUsage::CopySrc,
+            (PassKind::Transfer, true) => ResourceUsage::CopyDst,
+            (PassKind::Render, _) if is_writable_depth_stencil_attachment => {
+                ResourceUsage::DepthStencilWrite
+            }
+            (PassKind::Render, false) if is_depth_stencil_attachment => {
+                ResourceUsage::DepthStencilRead
+            }
+            (PassKind::Render, true) if is_depth_stencil_attachment => {
+                ResourceUsage::DepthStencilWrite
+            }
+            (PassKind::Render, _) if is_color_attachment => ResourceUsage::ColorAttachment,
+            (_, false) if is_texture => ResourceUsage::Sampled,
+            (_, false) => ResourceUsage::StorageRead,
+            (_, true) => ResourceUsage::StorageWrite,
+        }
+    }
+
+    ///把未剔除的渲染节点对资源的使用方式合并到临时资源的描述中
+    fn accumulate_usages(&mut self) {
+        for pass_node in self.pass_nodes.iter() {
+            if pass_node.is_culled() {
+                continue;
+            }
+
+            for (resource_node_handle, usage) in pass_node.usages.iter() {
+                let resource_node = &self.resource_nodes[resource_node_handle.index()];
+                let resource = &mut self.resources[resource_node.resource_handle.index()];
+
+                if let VirtualResourceState::Setup(desc) = &mut resource.state {
+                    desc.add_usage(*usage);
+                }
+            }
+        }
+    }
+
+    ///按执行顺序记录每个纹理范围的使用方式，在使用方式改变的地方插入屏障
+    ///
+    ///同一个渲染节点以相同方式读写同一个范围时，比如加载后写回的附件，只插入一次屏障
+    fn compute_barriers(&mut self) {
+        let mut states: HashMap<TypeHandle<VirtualResource>, Vec<TrackedUsage>> =
+            HashMap::default();
+
+        for pass_node_handle in self.sorted_pass_nodes.iter() {
+            let pass_node = &self.pass_nodes[pass_node_handle.index()];
+
+            if pass_node.is_culled() {
+                continue;
+            }
+
+            let accesses = pass_node
+                .reads
+                .iter()
+                .map(|handle| (*handle, pass_node.read_range(handle)))
+                .chain(
+                    pass_node
+                        .writes
+                        .iter()
+                        .map(|handle| (*handle, self.resource_nodes[handle.index()].range)),
+                );
+
+            let mut barriers = vec![];
+
+            for (resource_node_handle, range) in accesses {
+                let usage = pass_node.usages[&resource_node_handle];
+                let resource_handle =
+                    self.resource_nodes[resource_node_handle.index()].resource_handle;
+                let resource = &self.resources[resource_handle.index()];
+
+                let range = match resource.texture_desc() {
+                    Some(desc) => {
+                        range.resolve(desc.mip_level_count, desc.size.depth_or_array_layers)
+                    }
+                    None => TextureSubresourceRange::ALL,
+                };
+
+                let tracked = states.entry(resource_handle).or_default();
+                let mut untracked = vec![range];
+
+                for (tracked_range, tracked_usage, tracked_pass) in tracked.iter() {
+                    let Some(overlap) = tracked_range.intersection(&range) else {
+                        continue;
+                    };
+
+                    untracked = untracked
+                        .iter()
+                        .flat_map(|part| part.subtract(&overlap))
+                        .collect();
+
+                    let same_access = tracked_pass == pass_node_handle && *tracked_usage == usage;
+
+                    if !same_access && ResourceUsage::needs_barrier(*tracked_usage, usage) {
+                        barriers.push(BarrierInfo {
+                            resource: resource_handle,
+                            range: overlap,
+                            before: *tracked_usage,
+                            after: usage,
+                        });
+                    }
+                }
+
+                //导入资源的初始状态由外部决定
+                if !resource.is_imported() {
+                    barriers.extend(untracked.into_iter().map(|part| BarrierInfo {
+                        resource: resource_handle,
+                        range: part,
+                        before: ResourceUsage::Undefined,
+                        after: usage,
+                    }));
+                }
+
+                let mut next = tracked
+                    .drain(..)
+                    .flat_map(|(tracked_range, tracked_usage, tracked_pass)| {
+                        tracked_range
+                            .subtract(&range)
+                            .into_iter()
+                            .map(move |part| (part, tracked_usage, tracked_pass))
+                    })
+                    .collect::<Vec<_>>();
+                next.push((range, usage, *pass_node_handle));
+                *tracked = next;
+            }
+
+            self.pass_nodes[pass_node_handle.index()].barriers = barriers;
+        }
+    }
+
+    fn compute_resource_lifetime(&mut self) {
+        for pass_node_handle in self.sorted_pass_nodes.iter() {
+            let pass_node = &self.pass_nodes[pass_node_handle.index()];
+
+            if pass_node.is_culled() {
+                continue;
+            }
+
+            //更新渲染节点读取的资源节点所指向资源的生命周期
+            for resource_node_handle in pass_node.reads.iter() {
+                let resource_node = &self.resource_nodes[resource_node_handle.index()];
+                let resource = &mut self.resources[resource_node.resource_handle.index()];
+                resource.info.update_lifetime(pass_node.handle);
+            }
+
+            //更新渲染节点吸入的资源节点所指向资源的生命周期
+            for resource_node_handle in pass_node.writes.iter() {
+                let resource_node = &self.resource_nodes[resource_node_handle.index()];
+                let resource = &mut self.resources[resource_node.resource_handle.index()];
+                resource.info.update_lifetime(pass_node.handle);
+            }
+        }
+
+        //异步队列上的渲染节点和之后的渲染节点同时执行，使用的资源在第一个渲染节点申请，提交之后释放
+        let first_pass_node_handle = self
+            .sorted_pass_nodes
+            .iter()
+            .find(|handle| !self.pass_nodes[handle.index()].is_culled())
+            .copied();
+
+        //更新pass_node中资源使用的索引顺序
+        for resource_index in 0..self.resources.len() {
+            let resource = &self.resources[resource_index];
+            let info = resource.info.clone();
+
+            if info.first_pass_node_handle.is_none() || info.last_pass_node_handle.is_none() {
+                continue;
+            }
+
+            let request_pass_node_handle = if resource.cross_queue {
+                first_pass_node_handle.unwrap()
+            } else {
+                info.first_pass_node_handle.unwrap()
+            };
+            let request_pass_node = &mut self.pass_nodes[request_pass_node_handle.index()];
+            request_pass_node.resource_request_array.push(info.handle);
+
+            //跨帧资源在执行结束后交给 HistoryResources
+            if resource.persistent || resource.cross_queue {
+                continue;
+            }
+
+            let last_pass_node_handle = info.last_pass_node_handle.unwrap();
+            let last_pass_node = &mut self.pass_nodes[last_pass_node_handle.index()];
+            last_pass_node.resource_release_array.push(info.handle);
+        }
+    }
+}
+
+impl FrameGraph {
+    pub fn create<DescriptorType>(&mut self, name: &str, desc: DescriptorType) -> ResourceNodeHandle<DescriptorType::Resource>
+    where
+        DescriptorType: FGResourceDescriptor + TypeEquals<Other = <<DescriptorType as FGResourceDescriptor>::Resource as FGResource>::Descriptor>,
+    {
+        let resource_handle = self.new_handle(self.resources.len());
+
+        let resource: VirtualResource = VirtualResource::setup::<DescriptorType::Resource>(
+            name,
+            resource_handle,
+            TypeEquals::same(desc),
+        );
+
+        self.add_resource(resource)
+    }
+
+    ///导入外部资源，导入的资源不会被回收到 TransientResourceCache 中
+    pub fn import<ResourceType: FGResource>(
+        &mut self,
+        name: &str,
+        resource: Arc<ResourceType>,
+    ) -> ResourceNodeHandle<ResourceType> {
+        let resource_handle = self.new_handle(self.resources.len());
+
+        let resource = VirtualResource::imported(name, resource_handle, resource);
+
+        self.add_resource(resource)
+    }
+
+    pub fn import_texture(
+        &mut self,
+        name: &str,
+        texture: Arc<Texture>,
+    ) -> ResourceNodeHandle<Texture> {
+        self.import(name, texture)
+    }
+
+    pub fn import_buffer(&mut self, name: &str, buffer: Arc<Buffer>) -> ResourceNodeHandle<Buffer> {
+        self.import(name, buffer)
+    }
+
+    fn add_resource<ResourceType>(
+        &mut self,
+        resource: VirtualResource,
+    ) -> ResourceNodeHandle<ResourceType> {
+        let resource_handle = resource.info.handle;
+        let resource_info = resource.info.clone();
+        self.resources.push(resource);
+
+        let handle = self.create_resource_node(resource_info);
+
+        ResourceNodeHandle::new(handle, resource_handle)
+    }
+
+    ///句柄必须属于当前帧：代数与 epoch 相同，并且资源节点存在且指向句柄中的资源。
+    ///只比较索引无法发现 reset 之前保留下来、索引恰好仍然有效的句柄
+    pub fn is_valid_handle<ResourceType>(
+        &self,
+        resource_node_handle: &ResourceNodeHandle<ResourceType>,
+    ) -> bool {
+        if resource_node_handle.resource_node_handle().generation() != self.epoch
+            || resource_node_handle.resource_handle().generation() != self.epoch
+        {
+            return false;
+        }
+
+        self.resource_nodes
+            .get(resource_node_handle.resource_node_handle().index())
+            .is_some_and(|resource_node| {
+                resource_node.resource_handle == resource_node_handle.resource_handle()
+            })
+    }
+
+    pub(crate) fn push_setup_error(&mut self, error: RendererError) {
+        self.setup_errors.push(error);
+    }
+
+    ///名称已经被使用时返回 false
+    pub(crate) fn register_module_instance(&mut self, instance_name: &str) -> bool {
+        self.module_instances.insert(instance_name.to_string())
+    }
+
+    ///多个渲染节点写入同一个版本中互不重叠的范围时，比如立方体贴图的各个面，
+    ///后写入的版本基于先写入的版本，其余范围的内容和剔除时的依赖沿版本链传递。
+    ///范围重叠的写入仍然基于原来的版本，在 compile 时返回 DuplicateWrite
+    pub(crate) fn chained_write_source(
+        &self,
+        source_handle: TypeHandle<ResourceNode>,
+        range: TextureSubresourceRange,
+    ) -> TypeHandle<ResourceNode> {
+        let mut source_handle = source_handle;
+
+        while let Some(next) = self.resource_nodes.iter().find(|resource_node| {
+            resource_node.source_handle == Some(source_handle)
+                && resource_node.pass_node_writer_handle.is_some()
+        }) {
+            if next.range.overlaps(&range) {
+                break;
+            }
+
+            source_handle = next.handle;
+        }
+
+        source_handle
+    }
+
+    pub fn create_resource_node(
+        &mut self,
+        resource_info: ResourceInfo,
+    ) -> TypeHandle<ResourceNode> {
+        let resource_handle = resource_info.handle;
+        let version = resource_info.version();
+
+        let handle = self.new_handle(self.resource_nodes.len());
+
+        self.resource_nodes
+            .push(ResourceNode::new(handle, resource_handle, version));
+
+        handle
+    }
+
+    ///返回 setup 的返回值，通常为之后的渲染节点需要读取的资源节点
+    pub fn add_callback_pass<Data, Output, Setup, Execute>(
+        &mut self,
+        insert_point: usize,
+        name: &str,
+        setup: Setup,
+        execute: Execute,
+    ) -> Output
+    where
+        Data: Default + Send + 'static,
+        Setup: FnOnce(&mut PassNodeBuilder, &mut Data) -> Output,
+        Execute: FnOnce(&Data, &mut RenderContext) -> Result<(), RendererError> + Send + 'static,
+    {
+        self.add_callback_pass_with_kind(insert_point, name, PassKind::Render, setup, execute)
+    }
+
+    ///计算节点不会打开渲染通道，不能声明附件
+    pub fn add_compute_pass<Data, Output, Setup, Execute>(
+        &mut self,
+        insert_point: usize,
+        name: &str,
+        setup: Setup,
+        execute: Execute,
+    ) -> Output
+    where
+        Data: Default + Send + 'static,
+        Setup: FnOnce(&mut PassNodeBuilder, &mut Data) -> Output,
+        Execute: FnOnce(&Data, &mut RenderContext) -> Result<(), RendererError> + Send + 'static,
+    {
+        self.add_callback_pass_with_kind(insert_point, name, PassKind::Compute, setup, execute)
+    }
+
+    fn add_callback_pass_with_kind<Data, Output, Setup, Execute>(
+        &mut self,
+        insert_point: usize,
+        name: &str,
+        kind: PassKind,
+        setup: Setup,
+        execute: Execute,
+    ) -> Output
+    where
+        Data: Default + Send + 'static,
+        Setup: FnOnce(&mut PassNodeBuilder, &mut Data) -> Output,
+        Execute: FnOnce(&Data, &mut RenderContext) -> Result<(), RendererError> + Send + 'static,
+    {
+        self.add_pass_with_output(insert_point, name, kind, |builder| {
+            let mut data = Data::default();
+            let output = setup(builder, &mut data);
+
+            let pass: DynPass = Box::new(CallbackPass::new(data, execute));
+
+            (pass, output)
+        })
+    }
+
+    pub fn add_pass(&mut self, insert_point: usize, name: &str, pass: DynPass) {
+        self.add_pass_with_kind(insert_point, name, PassKind::Render, pass);
+    }
+
+    pub fn add_pass_with_kind(
+        &mut self,
+        insert_point: usize,
+        name: &str,
+        kind: PassKind,
+        mut pass: DynPass,
+    ) {
+        self.add_pass_with_output(insert_point, name, kind, |builder| {
+            pass.setup(builder);
+
+            (pass, ())
+        });
+    }
+
+    ///setup 使用 builder 声明资源并返回渲染节点和交给调用者的输出，
+    ///返回的渲染节点的 Pass::setup 不会被调用
+    pub fn add_pass_with_output<Output>(
+        &mut self,
+        insert_point: usize,
+        name: &str,
+        kind: PassKind,
+        setup: impl FnOnce(&mut PassNodeBuilder) -> (DynPass, Output),
+    ) -> Output {
+        let handle = self.get_current_pass_node_handle();
+        let mut builder = PassNodeBuilder::new(insert_point, name, handle, self);
+
+        let (pass, output) = setup(&mut builder);
+
+        let mut pass_node = builder.build();
+        pass_node.kind = kind;
+        pass_node.pass = Some(pass);
+
+        self.pass_nodes.push(pass_node);
+
+        output
+    }
+
+    pub fn aliasing_plan(&self) -> &AliasingPlan {
+        &self.aliasing_plan
+    }
+
+    ///最近一次 compile 的临时资源显存统计
+    pub fn transient_memory_report(&self) -> &TransientMemoryReport {
+        self.aliasing_plan.report()
+    }
+
+    pub fn pass_nodes(&self) -> &[PassNode] {
+        &self.pass_nodes
+    }
+
+    ///compile 之后按执行顺序排列的渲染节点
+    pub fn sorted_pass_nodes(&self) -> &[TypeHandle<PassNode>] {
+        &self.sorted_pass_nodes
+    }
+
+    pub fn resource_nodes(&self) -> &[ResourceNode] {
+        &self.resource_nodes
+    }
+
+    pub fn resources(&self) -> &[VirtualResource] {
+        &self.resources
+    }
+
+    ///每次 reset 后加一，本帧创建的句柄都带有当前的代数
+    pub fn epoch(&self) -> u32 {
+        self.epoch
+    }
+
+    pub fn get_current_pass_node_handle(&self) -> TypeHandle<PassNode> {
+        self.new_handle(self.pass_nodes.len())
+    }
+
+    fn new_handle<T>(&self, index: usize) -> TypeHandle<T> {
+        TypeHandle::with_generation(index, self.epoch)
+    }
+
+    ///索引越界或者句柄来自之前的帧时返回错误
+    fn check_handle<T>(
+        &self,
+        kind: &'static str,
+        handle: &TypeHandle<T>,
+        len: usize,
+    ) -> Result<(), RendererError> {
+        if handle.index() < len && handle.generation() == self.epoch {
+            Ok(())
+        } else {
+            Err(RendererError::InvalidHandle {
+                kind,
+                index: handle.index(),
+                generation: handle.generation(),
+                expected: self.epoch,
+            })
+        }
+    }
+
+    pub fn get_pass_node_mut(
+        &mut self,
+        handle: &TypeHandle<PassNode>,
+    ) -> Result<&mut PassNode, RendererError> {
+        self.check_handle("pass node", handle, self.pass_nodes.len())?;
+
+        Ok(self.pass_node_mut(handle))
+    }
+
+    pub fn get_pass_node(&self, handle: &TypeHandle<PassNode>) -> Result<&PassNode, RendererError> {
+        self.check_handle("pass node", handle, self.pass_nodes.len())?;
+
+        Ok(self.pass_node(handle))
+    }
+
+    pub fn get_resource_node(
+        &self,
+        handle: &TypeHandle<ResourceNode>,
+    ) -> Result<&ResourceNode, RendererError> {
+        self.check_handle("resource node", handle, self.resource_nodes.len())?;
+
+        Ok(self.resource_node(handle))
+    }
+
+    pub fn get_resource_node_mut(
+        &mut self,
+        handle: &TypeHandle<ResourceNode>,
+    ) -> Result<&mut ResourceNode, RendererError> {
+        self.check_handle("resource node", handle, self.resource_nodes.len())?;
+
+        Ok(self.resource_node_mut(handle))
+    }
+
+    pub fn get_resource(
+        &self,
+        handle: &TypeHandle<VirtualResource>,
+    ) -> Result<&VirtualResource, RendererError> {
+        self.check_handle("resource", handle, self.resources.len())?;
+
+        Ok(self.resource(handle))
+    }
+
+    pub fn get_resource_mut(
+        &mut self,
+        handle: &TypeHandle<VirtualResource>,
+    ) -> Result<&mut VirtualResource, RendererError> {
+        self.check_handle("resource", handle, self.resources.len())?;
+
+        Ok(self.resource_mut(handle))
+    }
+
+    //以下查找不检查代数，只用于本帧创建的句柄和编译结果中的句柄
+    pub(crate) fn pass_node(&self, handle: &TypeHandle<PassNode>) -> &PassNode {
+        &self.pass_nodes[handle.index()]
+    }
+
+    pub(crate) fn pass_node_mut(&mut self, handle: &TypeHandle<PassNode>) -> &mut PassNode {
+        &mut self.pass_nodes[handle.index()]
+    }
+
+    pub(crate) fn resource_node(&self, handle: &TypeHandle<ResourceNode>) -> &ResourceNode {
+        &self.resource_nodes[handle.index()]
+    }
+
+    pub(crate) fn resource_node_mut(
+        &mut self,
+        handle: &TypeHandle<ResourceNode>,
+    ) -> &mut ResourceNode {
+        &mut self.resource_nodes[handle.index()]
+    }
+
+    pub(crate) fn resource(&self, handle: &TypeHandle<VirtualResource>) -> &VirtualResource {
+        &self.resources[handle.index()]
+    }
+
+    pub(crate) fn resource_mut(
+        &mut self,
+        handle: &TypeHandle<VirtualResource>,
+    ) -> &mut VirtualResource {
+        &mut self.resources[handle.index()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        Buffer, Device, LoadOp, Operations, PassKind, ResourceUsage, StoreOp,
+        TextureSubresourceRange, TextureUsages, TransientResourceCache, error::RendererError,
+    };
+
+    use super::{
+        super::{
+            GpuRead, GpuWrite, ResourceNodeHandle, ResourceRef,
+            testing::{RecordingDevice, Submitted, add_pass, buffer_desc, texture_desc},
+        },
+        CompileOptions, ExecuteMode, FrameGraph,
+    };
+
+    fn sorted_passes(fg: &FrameGraph) -> Vec<&str> {
+        fg.sorted_pass_nodes()
+            .iter()
+            .map(|handle| fg.pass_node(handle).name.as_str())
+            .collect()
+    }
+
+    fn culled_passes(fg: &FrameGraph) -> Vec<&str> {
+        fg.pass_nodes()
+            .iter()
+            .filter(|pass_node| pass_node.is_culled())
+            .map(|pass_node| pass_node.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn passes_without_readers_are_culled() {
+        let mut fg = FrameGraph::default();
+        let depth = fg.create("depth", texture_desc(64));
+        let debug = fg.create("debug", texture_desc(64));
+        let shadow = fg.create("shadow", texture_desc(64));
+
+        let depth = add_pass(&mut fg, 0, "depth_prepass", PassKind::Render, |builder| {
+            builder.write(depth).handle()
+        });
+        //只有 debug_view 读取 depth，debug_view 被剔除之后 depth_prepass 也被剔除
+        add_pass(&mut fg, 1, "debug_view", PassKind::Render, |builder| {
+            builder.read(depth);
+            builder.write(debug);
+        });
+        let shadow = add_pass(&mut fg, 2, "shadow", PassKind::Render, |builder| {
+            builder.write(shadow).handle()
+        });
+        add_pass(&mut fg, 3, "lighting", PassKind::Render, |builder| {
+            builder.read(shadow);
+            builder.side_effect();
+        });
+
+        fg.compile().unwrap();
+
+        assert_eq!(culled_passes(&fg), vec!["depth_prepass", "debug_view"]);
+    }
+
+    #[test]
+    fn passes_writing_imported_resources_are_kept() {
+        let device = Device::new(RecordingDevice::default());
+        let mut fg = FrameGraph::default();
+        let swapchain = fg.import_texture(
+            "swapchain",
+            Arc::new(device.create_texture(texture_desc(64))),
+        );
+        let scratch = fg.create("scratch", texture_desc(64));
+
+        add_pass(&mut fg, 0, "present", PassKind::Render, |builder| {
+            builder.write(swapchain);
+        });
+        add_pass(&mut fg, 1, "scratch", PassKind::Render, |builder| {
+            builder.write(scratch);
+        });
+
+        fg.compile().unwrap();
+
+        assert_eq!(culled_passes(&fg), vec!["scratch"]);
+        assert!(fg.pass_nodes()[0].side_effect);
+    }
+
+    #[test]
+    fn culled_passes_are_not_executed() {
+        let recording = RecordingDevice::default();
+        let device = Device::new(recording.clone());
+        let mut transient_resource_cache = TransientResourceCache::default();
+
+        let mut fg = FrameGraph::default();
+        let unused = fg.create("unused", texture_desc(64));
+        let output = fg.create("output", texture_desc(64));
+
+        add_pass(&mut fg, 0, "unused", PassKind::Render, |builder| {
+            builder.write(unused);
+        });
+        add_pass(&mut fg, 1, "output", PassKind::Render, |builder| {
+            builder.write(output);
+            builder.side_effect();
+        });
+
+        fg.compile().unwrap();
+        fg.execute(&device, &mut transient_resource_cache).unwrap();
+
+        let log = recording.take_log();
+        assert!(log.contains(&"push_debug_group output".to_string()));
+        assert!(!log.contains(&"push_debug_group unused".to_string()));
+        //被剔除的渲染节点使用的资源不会被创建
+        assert!(!log.contains(&"create_texture unused".to_string()));
+    }
+
+    #[test]
+    fn dependencies_order_before_insert_point() {
+        let mut fg = FrameGraph::default();
+        let gbuffer = fg.create("gbuffer", texture_desc(64));
+        let ui = fg.create("ui", texture_desc(64));
+
+        let gbuffer = add_pass(&mut fg, 5, "gbuffer", PassKind::Render, |builder| {
+            builder.write(gbuffer).handle()
+        });
+        add_pass(&mut fg, 0, "lighting", PassKind::Render, |builder| {
+            builder.read(gbuffer);
+            builder.side_effect();
+        });
+        //没有依赖的渲染节点按 insert_point 排序
+        add_pass(&mut fg, 1, "ui", PassKind::Render, |builder| {
+            builder.write(ui);
+            builder.side_effect();
+        });
+
+        fg.compile().unwrap();
+
+        assert_eq!(sorted_passes(&fg), vec!["ui", "gbuffer", "lighting"]);
+    }
+
+    #[test]
+    fn readers_run_before_the_next_write() {
+        let mut fg = FrameGraph::default();
+        let color = fg.create("color", texture_desc(64));
+
+        let color = add_pass(&mut fg, 0, "draw", PassKind::Render, |builder| {
+            builder.write(color).handle()
+        });
+        let first = color.clone();
+        //overlay 写入 color 的新版本，必须等待读取旧版本的 bloom
+        add_pass(&mut fg, 1, "overlay", PassKind::Render, |builder| {
+            builder.write(color);
+            builder.side_effect();
+        });
+        add_pass(&mut fg, 0, "bloom", PassKind::Render, |builder| {
+            builder.read(first);
+            builder.side_effect();
+        });
+
+        fg.compile().unwrap();
+
+        assert_eq!(sorted_passes(&fg), vec!["draw", "bloom", "overlay"]);
+    }
+
+    #[test]
+    fn dependency_cycle_is_reported() {
+        let mut fg = FrameGraph::default();
+        let color = fg.create("color", texture_desc(64));
+        let mask = fg.create("mask", texture_desc(64));
+
+        let color = add_pass(&mut fg, 0, "a", PassKind::Render, |builder| {
+            builder.write(color).handle()
+        });
+        let first = color.clone();
+        //b 写入 color 的新版本，c 读取旧版本并且读取 b 的输出
+        let mask = add_pass(&mut fg, 1, "b", PassKind::Render, |builder| {
+            builder.write(color);
+            builder.write(mask).handle()
+        });
+        add_pass(&mut fg, 2, "c", PassKind::Render, |builder| {
+            builder.read(first);
+            builder.read(mask);
+            builder.side_effect();
+        });
+
+        assert!(matches!(
+            fg.compile(),
+            Err(RendererError::DependencyCycle { passes }) if passes == ["b", "c"]
+        ));
+    }
+
+    #[test]
+    fn reading_an_unwritten_resource_is_rejected() {
+        let mut fg = FrameGraph::default();
+        let history = fg.create("history", texture_desc(64));
+
+        add_pass(&mut fg, 0, "taa", PassKind::Render, |builder| {
+            builder.read(history);
+            builder.side_effect();
+        });
+
+        assert!(matches!(
+            fg.compile(),
+            Err(RendererError::ReadUnwrittenResource { pass, resource })
+                if pass == "taa" && resource == "history"
+        ));
+    }
+
+    #[test]
+    fn writing_the_same_version_twice_is_rejected() {
+        let mut fg = FrameGraph::default();
+        let color = fg.create("color", texture_desc(64));
+        let same = color.clone();
+
+        add_pass(&mut fg, 0, "opaque", PassKind::Render, |builder| {
+            builder.write(color);
+            builder.side_effect();
+        });
+        add_pass(&mut fg, 1, "transparent", PassKind::Render, |builder| {
+            builder.write(same);
+            builder.side_effect();
+        });
+
+        assert!(matches!(
+            fg.compile(),
+            Err(RendererError::DuplicateWrite { resource, version: 0, first_pass, second_pass })
+                if resource == "color" && first_pass == "opaque" && second_pass == "transparent"
+        ));
+    }
+
+    #[test]
+    fn disjoint_writes_of_the_same_version_are_chained() {
+        let mut fg = FrameGraph::default();
+        let mut desc = texture_desc(64);
+        desc.size.depth_or_array_layers = 2;
+        let cube = fg.create("cube", desc);
+        let same = cube.clone();
+
+        let face1 = add_pass(&mut fg, 1, "face1", PassKind::Compute, |builder| {
+            builder
+                .write_subresource(cube, TextureSubresourceRange::array_layer(1))
+                .handle()
+        });
+        let face0 = add_pass(&mut fg, 0, "face0", PassKind::Compute, |builder| {
+            builder
+                .write_subresource(same, TextureSubresourceRange::array_layer(0))
+                .handle()
+        });
+        add_pass(&mut fg, 2, "present", PassKind::Compute, |builder| {
+            builder.read(face0.clone());
+            builder.side_effect();
+        });
+
+        fg.compile().unwrap();
+
+        //face0 基于 face1 写入的版本，但是两者的范围不重叠，之间没有依赖
+        assert_eq!(
+            fg.resource_nodes()[face0.resource_node_handle().index()].source_handle,
+            Some(face1.resource_node_handle())
+        );
+        assert_eq!(sorted_passes(&fg), vec!["face0", "face1", "present"]);
+        assert!(culled_passes(&fg).is_empty());
+    }
+
+    #[test]
+    fn writing_a_mip_does_not_wait_for_reads_of_other_mips() {
+        let mut fg = FrameGraph::default();
+        let mut desc = texture_desc(64);
+        desc.mip_level_count = 2;
+        let bloom = fg.create("bloom", desc);
+
+        let bloom = add_pass(&mut fg, 0, "mip0", PassKind::Compute, |builder| {
+            builder
+                .write_subresource(bloom, TextureSubresourceRange::mip_level(0))
+                .handle()
+        });
+        let mip1 = bloom.clone();
+        add_pass(&mut fg, 2, "blur", PassKind::Compute, |builder| {
+            builder.read_subresource(bloom, TextureSubresourceRange::mip_level(0));
+            builder.side_effect();
+        });
+        add_pass(&mut fg, 1, "mip1", PassKind::Compute, |builder| {
+            builder.read_subresource(mip1.clone(), TextureSubresourceRange::mip_level(0));
+            builder.write_subresource(mip1, TextureSubresourceRange::mip_level(1));
+            builder.side_effect();
+        });
+
+        fg.compile().unwrap();
+
+        //blur 读取 mip 0，mip1 写入 mip 1，按 insert_point 排序
+        assert_eq!(sorted_passes(&fg), vec!["mip0", "mip1", "blur"]);
+        assert_eq!(
+            pass_accesses(&fg, "mip1").1,
+            vec![
+                (ResourceUsage::StorageWrite, ResourceUsage::Sampled),
+                (ResourceUsage::Undefined, ResourceUsage::StorageWrite),
+            ]
+        );
+        assert_eq!(
+            fg.pass_nodes()[2].barriers[1].range,
+            TextureSubresourceRange::mip_level(1).resolve(2, 1)
+        );
+        //mip1 之后 mip 0 仍然处于采样状态，blur 不需要屏障
+        assert!(pass_accesses(&fg, "blur").1.is_empty());
+    }
+
+    #[test]
+    fn handles_kept_across_reset_are_rejected() {
+        let mut fg = FrameGraph::default();
+        let stale = fg.create("color", texture_desc(64));
+        fg.reset();
+
+        //新的一帧中相同索引的资源节点仍然存在，只有代数不同
+        fg.create("color", texture_desc(64));
+        add_pass(&mut fg, 0, "draw", PassKind::Render, |builder| {
+            builder.write(stale);
+            builder.side_effect();
+        });
+
+        assert!(fg.pass_nodes()[0].writes.is_empty());
+        assert!(matches!(
+            fg.compile(),
+            Err(RendererError::StaleResourceHandle { pass, index: 0 }) if pass == "draw"
+        ));
+    }
+
+    #[test]
+    fn lookups_and_reads_of_handles_kept_across_reset_are_rejected() {
+        let mut fg = FrameGraph::default();
+        let stale = fg.create("color", texture_desc(64));
+        let stale_write = stale.clone();
+        add_pass(&mut fg, 0, "draw", PassKind::Render, |builder| {
+            builder.write(stale_write);
+            builder.side_effect();
+        });
+        let stale_pass = fg.pass_nodes()[0].handle;
+        let stale_epoch = fg.epoch();
+        fg.reset();
+
+        fg.create("color", texture_desc(64));
+        let stale_read = stale.clone();
+        add_pass(&mut fg, 0, "draw", PassKind::Render, |builder| {
+            builder.read(stale_read);
+            builder.side_effect();
+        });
+
+        let is_stale = |result: Result<(), RendererError>, expected_kind: &str| {
+            matches!(
+                result,
+                Err(RendererError::InvalidHandle { kind, index: 0, generation, expected })
+                    if kind == expected_kind && generation == stale_epoch && expected == fg.epoch()
+            )
+        };
+        assert!(is_stale(
+            fg.get_resource_node(&stale.resource_node_handle())
+                .map(|_| ()),
+            "resource node"
+        ));
+        assert!(is_stale(
+            fg.get_resource(&stale.resource_handle()).map(|_| ()),
+            "resource"
+        ));
+        assert!(is_stale(
+            fg.get_pass_node(&stale_pass).map(|_| ()),
+            "pass node"
+        ));
+
+        assert!(fg.pass_nodes()[0].reads.is_empty());
+        assert!(matches!(
+            fg.compile(),
+            Err(RendererError::StaleResourceHandle { pass, index: 0 }) if pass == "draw"
+        ));
+    }
+
+    ///transparent 加载 opaque 的颜色附件，两者各自写入一个描述相同的缓冲区
+    fn build_merged_graph(fg: &mut FrameGraph) {
+        fg.set_compile_options(CompileOptions {
+            merge_passes: true,
+            ..Default::default()
+        });
+
+        let color = fg.create("color", texture_desc(64));
+        let opaque_data = fg.create("opaque_data", buffer_desc());
+        let transparent_data = fg.create("transparent_data", buffer_desc());
+
+        let color = add_pass(fg, 0, "opaque", PassKind::Render, |builder| {
+            let color = builder.write(color);
+            builder.add_color_attachment(&color, Operations::default());
+            builder.write(opaque_data);
+            color.handle()
+        });
+
+        let (color, transparent_data) =
+            add_pass(fg, 1, "transparent", PassKind::Render, |builder| {
+                builder.read(color.clone());
+                let color = builder.write(color);
+                builder.add_color_attachment(
+                    &color,
+                    Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    },
+                );
+                (color.handle(), builder.write(transparent_data).handle())
+            });
+
+        add_pass(fg, 2, "present", PassKind::Render, |builder| {
+            builder.read(color);
+            builder.read(transparent_data);
+            builder.side_effect();
+        });
+    }
+
+    #[test]
+    fn compatible_passes_are_merged() {
+        let mut fg = FrameGraph::default();
+        build_merged_graph(&mut fg);
+        fg.compile().unwrap();
+
+        assert_eq!(fg.merged_passes(), [vec!["opaque", "transparent"]]);
+
+        let recording = RecordingDevice::default();
+        let device = Device::new(recording.clone());
+        fg.execute(&device, &mut TransientResourceCache::default())
+            .unwrap();
+
+        let passes = recording
+            .take_log()
+            .into_iter()
+            .filter(|entry| {
+                entry.ends_with("_render_pass")
+                    || entry == "next_subpass"
+                    || entry.starts_with("push_debug_group")
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            passes,
+            [
+                "begin_render_pass",
+                "push_debug_group opaque",
+                "next_subpass",
+                "push_debug_group transparent",
+                "end_render_pass",
+                "begin_render_pass",
+                "push_debug_group present",
+                "end_render_pass",
+            ]
+        );
+    }
+
+    #[test]
+    fn merged_passes_do_not_share_memory() {
+        let mut fg = FrameGraph::default();
+        build_merged_graph(&mut fg);
+        fg.compile().unwrap();
+
+        //两个子通道的资源在渲染通道开始之前同时申请
+        let opaque_data = &fg.resources()[1];
+        let transparent_data = &fg.resources()[2];
+        assert_eq!(fg.aliasing_plan().slots().len(), 3);
+        assert_ne!(
+            fg.aliasing_plan()
+                .get_slot(&opaque_data.info.handle)
+                .unwrap()
+                .resources,
+            fg.aliasing_plan()
+                .get_slot(&transparent_data.info.handle)
+                .unwrap()
+                .resources,
+        );
+        assert_eq!(
+            fg.transient_memory_report().bytes_with_aliasing,
+            fg.transient_memory_report().bytes_without_aliasing
+        );
+
+        let recording = RecordingDevice::default();
+        let device = Device::new(recording.clone());
+        fg.execute(&device, &mut TransientResourceCache::default())
+            .unwrap();
+
+        let created = recording
+            .take_log()
+            .into_iter()
+            .filter(|entry| entry.starts_with("create_"))
+            .count();
+        assert_eq!(created, 3);
+    }
+
+    ///节点按读写顺序的使用方式和屏障
+    fn pass_accesses(
+        fg: &FrameGraph,
+        name: &str,
+    ) -> (Vec<ResourceUsage>, Vec<(ResourceUsage, ResourceUsage)>) {
+        let pass_node = fg
+            .pass_nodes()
+            .iter()
+            .find(|pass_node| pass_node.name == name)
+            .unwrap();
+
+        let usages = pass_node
+            .reads
+            .iter()
+            .chain(pass_node.writes.iter())
+            .map(|handle| pass_node.usages[handle])
+            .collect();
+        let barriers = pass_node
+            .barriers
+            .iter()
+            .map(|barrier| (barrier.before, barrier.after))
+            .collect();
+
+        (usages, barriers)
+    }
+
+    #[test]
+    fn loaded_attachments_are_not_sampled() {
+        let mut fg = FrameGraph::default();
+        let color = fg.create("color", texture_desc(64));
+        let depth = fg.create("depth", texture_desc(64));
+        fn load<V>() -> Operations<V> {
+            Operations {
+                load: LoadOp::Load,
+                store: StoreOp::Store,
+            }
+        }
+
+        let (color, depth) = add_pass(&mut fg, 0, "opaque", PassKind::Render, |builder| {
+            let color = builder.write(color);
+            let depth = builder.write(depth);
+            builder.add_color_attachment(&color, Operations::default());
+            builder.set_depth_stencil_attachment(&depth, Some(Operations::default()), None);
+            (color.handle(), depth.handle())
+        });
+        add_pass(&mut fg, 1, "transparent", PassKind::Render, |builder| {
+            builder.read(color.clone());
+            builder.read(depth.clone());
+            let color = builder.write(color);
+            let depth = builder.write(depth);
+            builder.add_color_attachment(&color, load());
+            builder.set_depth_stencil_attachment(&depth, Some(load()), None);
+            builder.side_effect();
+        });
+
+        fg.compile().unwrap();
+
+        assert_eq!(
+            pass_accesses(&fg, "opaque"),
+            (
+                vec![
+                    ResourceUsage::ColorAttachment,
+                    ResourceUsage::DepthStencilWrite
+                ],
+                vec![
+                    (ResourceUsage::Undefined, ResourceUsage::ColorAttachment),
+                    (ResourceUsage::Undefined, ResourceUsage::DepthStencilWrite),
+                ]
+            )
+        );
+        //加载和写回是同一次附件使用，每个附件只需要一个写后写屏障
+        assert_eq!(
+            pass_accesses(&fg, "transparent"),
+            (
+                vec![
+                    ResourceUsage::ColorAttachment,
+                    ResourceUsage::DepthStencilWrite,
+                    ResourceUsage::ColorAttachment,
+                    ResourceUsage::DepthStencilWrite
+                ],
+                vec![
+                    (
+                        ResourceUsage::ColorAttachment,
+                        ResourceUsage::ColorAttachment
+                    ),
+                    (
+                        ResourceUsage::DepthStencilWrite,
+                        ResourceUsage::DepthStencilWrite
+                    ),
+                ]
+            )
+        );
+        for resource in fg.resources() {
+            assert_eq!(
+                resource.texture_desc().unwrap().usage,
+                TextureUsages::RENDER_ATTACHMENT
+            );
+        }
+    }
+
+    #[test]
+    fn reads_after_attachment_writes_are_transitioned() {
+        let mut fg = FrameGraph::default();
+        let color = fg.create("color", texture_desc(64));
+        let depth = fg.create("depth", texture_desc(64));
+        let output = fg.create("output", texture_desc(64));
+
+        let (color, depth) = add_pass(&mut fg, 0, "opaque", PassKind::Render, |builder| {
+            let color = builder.write(color);
+            let depth = builder.write(depth);
+            builder.add_color_attachment(&color, Operations::default());
+            builder.set_depth_stencil_attachment(&depth, Some(Operations::default()), None);
+            (color.handle(), depth.handle())
+        });
+        add_pass(&mut fg, 1, "post", PassKind::Render, |builder| {
+            builder.read(color);
+            let depth = builder.read(depth);
+            builder.set_depth_stencil_attachment(&depth, None, None);
+            let output = builder.write(output);
+            builder.add_color_attachment(&output, Operations::default());
+            builder.side_effect();
+        });
+
+        fg.compile().unwrap();
+
+        assert_eq!(
+            pass_accesses(&fg, "post"),
+            (
+                vec![
+                    ResourceUsage::Sampled,
+                    ResourceUsage::DepthStencilRead,
+                    ResourceUsage::ColorAttachment
+                ],
+                vec![
+                    (ResourceUsage::ColorAttachment, ResourceUsage::Sampled),
+                    (
+                        ResourceUsage::DepthStencilWrite,
+                        ResourceUsage::DepthStencilRead
+                    ),
+                    (ResourceUsage::Undefined, ResourceUsage::ColorAttachment),
+                ]
+            )
+        );
+
+        let usage = |index: usize| fg.resources()[index].texture_desc().unwrap().usage;
+        assert_eq!(
+            usage(0),
+            TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING
+        );
+        assert_eq!(usage(1), TextureUsages::RENDER_ATTACHMENT);
+    }
+
+    #[derive(Default)]
+    struct BufferPassData {
+        inputs: Vec<ResourceRef<Buffer, GpuRead>>,
+        output: Option<ResourceRef<Buffer, GpuWrite>>,
+    }
+
+    ///写入 name 缓冲区的计算节点，执行时读取 inputs 并修改写入的缓冲区
+    fn add_buffer_pass(
+        fg: &mut FrameGraph,
+        insert_point: usize,
+        name: &str,
+        inputs: Vec<ResourceNodeHandle<Buffer>>,
+    ) -> ResourceNodeHandle<Buffer> {
+        let output = fg.create(name, buffer_desc());
+
+        fg.add_compute_pass(
+            insert_point,
+            name,
+            |builder, data: &mut BufferPassData| {
+                data.inputs = inputs
+                    .into_iter()
+                    .map(|input| builder.read(input))
+                    .collect();
+                let output = builder.write(output);
+                let handle = output.handle();
+                data.output = Some(output);
+
+                handle
+            },
+            |data, render_context| {
+                for input in data.inputs.iter() {
+                    render_context.get(input)?;
+                }
+                render_context.get_mut(data.output.as_ref().unwrap())?;
+
+                Ok(())
+            },
+        )
+    }
+
+    ///读取 input 并写入 name 缓冲区，执行时返回错误
+    fn add_failing_pass(
+        fg: &mut FrameGraph,
+        insert_point: usize,
+        name: &str,
+        input: ResourceNodeHandle<Buffer>,
+    ) -> ResourceNodeHandle<Buffer> {
+        let output = fg.create(name, buffer_desc());
+        let pass = name.to_string();
+
+        fg.add_compute_pass(
+            insert_point,
+            name,
+            |builder, _: &mut ()| {
+                builder.read(input);
+                builder.write(output).handle()
+            },
+            move |_, _| Err(RendererError::CommandBufferNotAvailable { pass: pass.clone() }),
+        )
+    }
+
+    fn add_present_pass(fg: &mut FrameGraph, inputs: Vec<ResourceNodeHandle<Buffer>>) {
+        add_pass(fg, 9, "present", PassKind::Compute, |builder| {
+            for input in inputs {
+                builder.read(input);
+            }
+            builder.side_effect();
+        });
+    }
+
+    ///a -> b -> c -> d，每个渲染节点读取上一个渲染节点写入的缓冲区
+    fn build_chain(fg: &mut FrameGraph) {
+        let mut previous = vec![];
+        for (insert_point, name) in ["a", "b", "c", "d"].into_iter().enumerate() {
+            previous = vec![add_buffer_pass(fg, insert_point, name, previous)];
+        }
+
+        add_present_pass(fg, previous);
+    }
+
+    ///a、b、c 互相独立，present 读取所有结果
+    fn build_fan_in(fg: &mut FrameGraph) {
+        let outputs = ["a", "b", "c"]
+            .into_iter()
+            .enumerate()
+            .map(|(insert_point, name)| add_buffer_pass(fg, insert_point, name, vec![]))
+            .collect();
+
+        add_present_pass(fg, outputs);
+    }
+
+    ///c 与 b 没有读写冲突，但是 c 复用 a 的共享内存，a 在 b 之后才释放
+    fn build_reuse(fg: &mut FrameGraph) {
+        let a = add_buffer_pass(fg, 0, "a", vec![]);
+        let b = add_buffer_pass(fg, 1, "b", vec![a]);
+        let c = add_buffer_pass(fg, 2, "c", vec![]);
+
+        add_present_pass(fg, vec![b, c]);
+    }
+
+    fn wave_sizes(fg: &mut FrameGraph) -> Vec<usize> {
+        let device_passes = fg.device_passes.take().unwrap();
+
+        fg.parallel_waves(device_passes)
+            .iter()
+            .map(|wave| wave.len())
+            .collect()
+    }
+
+    #[test]
+    fn parallel_waves_split_on_conflicts_and_shared_memory() {
+        let mut fg = FrameGraph::default();
+        build_chain(&mut fg);
+        fg.compile().unwrap();
+        assert_eq!(wave_sizes(&mut fg), [1, 1, 1, 1, 1]);
+
+        let mut fg = FrameGraph::default();
+        build_fan_in(&mut fg);
+        fg.compile().unwrap();
+        assert_eq!(wave_sizes(&mut fg), [3, 1]);
+
+        let mut fg = FrameGraph::default();
+        build_reuse(&mut fg);
+        fg.compile().unwrap();
+        assert_eq!(fg.aliasing_plan().slots().len(), 2);
+        assert_eq!(wave_sizes(&mut fg), [1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn parallel_execution_mutates_written_resources_and_reuses_memory() {
+        for build in [build_chain, build_fan_in, build_reuse] {
+            let recording = RecordingDevice::default();
+            let device = Device::new(recording.clone());
+            let mut fg = FrameGraph::default();
+            fg.set_execute_mode(ExecuteMode::Parallel { worker_count: 4 });
+
+            build(&mut fg);
+            fg.compile().unwrap();
+            fg.execute(&device, &mut TransientResourceCache::default())
+                .unwrap();
+
+            //与串行执行相同，每块共享内存只创建一次
+            let created = recording
+                .take_log()
+                .into_iter()
+                .filter(|entry| entry.starts_with("create_buffer"))
+                .count();
+            assert_eq!(created, fg.aliasing_plan().slots().len());
+            assert_eq!(
+                recording.take_submitted(),
+                [Submitted::Serial(fg.pass_nodes().len())]
+            );
+        }
+    }
+
+    #[test]
+    fn resources_are_returned_to_the_cache_when_a_pass_fails() {
+        for execute_mode in [
+            ExecuteMode::Serial,
+            ExecuteMode::Parallel { worker_count: 4 },
+        ] {
+            let recording = RecordingDevice::default();
+            let device = Device::new(recording.clone());
+            let mut transient_resource_cache = TransientResourceCache::default();
+            let mut fg = FrameGraph::default();
+            fg.set_execute_mode(execute_mode);
+
+            let a = add_buffer_pass(&mut fg, 0, "a", vec![]);
+            let b = add_failing_pass(&mut fg, 1, "b", a);
+            add_present_pass(&mut fg, vec![b]);
+            fg.compile().unwrap();
+
+            assert!(matches!(
+                fg.execute(&device, &mut transient_resource_cache),
+                Err(RendererError::CommandBufferNotAvailable { pass }) if pass == "b"
+            ));
+            assert!(recording.take_submitted().is_empty());
+            assert_eq!(
+                transient_resource_cache.stats().resource_count,
+                fg.aliasing_plan().slots().len()
+            );
+        }
     }
 }
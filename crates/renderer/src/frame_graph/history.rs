@@ -0,0 +1,407 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    Texture, TextureDescriptor, TextureUsages, error::RendererError, gfx_base::TypeHandle,
+};
+
+use super::{
+    AnyFGResource, AnyFGResourceDescriptor, FrameGraph, ImportedVirtualResource,
+    ResourceNodeHandle, ResourceTable, VirtualResource, VirtualResourceState,
+};
+
+///本帧声明的历史纹理
+pub struct HistoryTexture {
+    ///本帧写入的纹理，下一帧作为 previous 导入
+    pub current: ResourceNodeHandle<Texture>,
+    ///上一帧写入的只读纹理，第一帧或者描述改变之后为空
+    pub previous: Option<ResourceNodeHandle<Texture>>,
+}
+
+struct HistoryEntry {
+    desc: TextureDescriptor,
+    ///之前所有帧中 current 和 previous 的使用方式，创建纹理时加入描述
+    usage: TextureUsages,
+    ///上一帧写入的纹理
+    previous: Option<Arc<Texture>>,
+    ///本帧可以写入的纹理，与 previous 交替使用
+    spare: Option<Arc<Texture>>,
+    ///本帧声明的资源，执行结束后取出
+    current: Option<TypeHandle<VirtualResource>>,
+    ///本帧导入的上一帧纹理
+    previous_resource: Option<TypeHandle<VirtualResource>>,
+    ///本帧是否声明过
+    used: bool,
+}
+
+impl HistoryEntry {
+    ///previous 通常在下一帧被采样，第一帧创建时还不知道之后的使用方式
+    fn new(desc: TextureDescriptor) -> Self {
+        HistoryEntry {
+            usage: desc.usage | TextureUsages::TEXTURE_BINDING,
+            desc,
+            previous: None,
+            spare: None,
+            current: None,
+            previous_resource: None,
+            used: false,
+        }
+    }
+
+    ///使用方式增加之后，之前创建的纹理不能再使用
+    fn discard_incompatible_textures(&mut self) {
+        let usage = self.usage;
+        let compatible = |texture: &Arc<Texture>| texture.get_desc().usage.contains(usage);
+
+        self.previous = self.previous.take().filter(compatible);
+        self.spare = self.spare.take().filter(compatible);
+    }
+}
+
+///跨帧保留的历史资源，一帧没有声明的历史资源会被释放
+#[derive(Default)]
+pub struct HistoryResources {
+    entries: HashMap<String, HistoryEntry>,
+}
+
+impl HistoryResources {
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    ///执行结束后轮换纹理，本帧没有写入的历史资源不再有上一帧的数据
+    pub(crate) fn update(
+        &mut self,
+        resources: &[VirtualResource],
+        resource_table: &mut ResourceTable,
+    ) {
+        for entry in self.entries.values_mut() {
+            entry.previous_resource = None;
+
+            let Some(handle) = entry.current.take() else {
+                continue;
+            };
+
+            let resource = &resources[handle.index()];
+
+            let texture = match &resource.state {
+                VirtualResourceState::Imported(state) => match &state.resource {
+                    ImportedVirtualResource::Texture(texture) => Some(texture.clone()),
+                    ImportedVirtualResource::Buffer(_) => None,
+                },
+                VirtualResourceState::Setup(_) => match resource_table.take_resource(&handle) {
+                    Some(AnyFGResource::OwnedTexture(texture)) => Some(Arc::new(texture)),
+                    _ => None,
+                },
+            };
+
+            let written = resource.info.first_pass_node_handle.is_some();
+
+            match texture {
+                Some(texture) if written => {
+                    entry.spare = entry.previous.take();
+                    entry.previous = Some(texture);
+                }
+                _ => {
+                    entry.previous = None;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn end_frame(&mut self) {
+        self.entries.retain(|_, entry| entry.used);
+
+        for entry in self.entries.values_mut() {
+            //声明之后没有执行，上一帧的数据已经过期
+            if entry.current.take().is_some() {
+                entry.previous = None;
+            }
+
+            entry.previous_resource = None;
+
+            entry.used = false;
+        }
+    }
+}
+
+impl FrameGraph {
+    ///声明跨帧保留的纹理，描述改变时之前的纹理会被丢弃。
+    ///纹理的使用方式包括之前所有帧的使用方式。上一帧的纹理缺少本帧新增的使用方式时 compile 返回错误，
+    ///下一帧重新创建
+    pub fn create_history_texture(
+        &mut self,
+        name: &str,
+        desc: TextureDescriptor,
+    ) -> HistoryTexture {
+        let mut entry = self
+            .history_mut()
+            .entries
+            .remove(name)
+            .filter(|entry| entry.desc == desc)
+            .unwrap_or_else(|| HistoryEntry::new(desc.clone()));
+        entry.discard_incompatible_textures();
+
+        if entry.used {
+            self.push_setup_error(RendererError::DuplicateHistoryResource {
+                name: name.to_string(),
+            });
+        }
+
+        let previous = entry.previous.clone().map(|texture| {
+            let handle = self.import(&format!("{name} (previous)"), texture);
+            self.resource_mut(&handle.resource_handle()).read_only = true;
+
+            handle
+        });
+
+        let current = match entry.spare.clone() {
+            Some(texture) => self.import(name, texture),
+            None => {
+                let desc = TextureDescriptor {
+                    usage: entry.usage,
+                    ..desc
+                };
+                let handle = self.create(name, desc);
+                self.resource_mut(&handle.resource_handle()).persistent = true;
+
+                handle
+            }
+        };
+
+        entry.current = Some(current.resource_handle());
+        entry.previous_resource = previous.as_ref().map(|handle| handle.resource_handle());
+        entry.used = true;
+        self.history_mut().entries.insert(name.to_string(), entry);
+
+        HistoryTexture { current, previous }
+    }
+
+    ///compile 开始时把本帧对历史纹理的使用方式加入记录。本帧写入的纹理总是包含记录的所有使用方式，
+    ///复用的纹理缺少时重新创建；上一帧的纹理缺少本帧的使用方式时数据无法使用，返回错误
+    pub(crate) fn prepare_history_textures(&mut self) -> Result<(), RendererError> {
+        let mut usages: HashMap<TypeHandle<VirtualResource>, TextureUsages> = HashMap::default();
+
+        for pass_node in self.pass_nodes().iter() {
+            for (resource_node_handle, usage) in pass_node.usages.iter() {
+                let resource_handle = self.resource_node(resource_node_handle).resource_handle;
+                *usages.entry(resource_handle).or_default() |= usage.texture_usages();
+            }
+        }
+
+        let mut currents = vec![];
+        let mut error = None;
+
+        for (name, entry) in self.history_mut().entries.iter_mut() {
+            for handle in entry.current.iter().chain(entry.previous_resource.iter()) {
+                entry.usage |= usages.get(handle).copied().unwrap_or_default();
+            }
+
+            let lacking = entry
+                .previous
+                .as_ref()
+                .zip(entry.previous_resource)
+                .map(|(texture, handle)| {
+                    let usage = usages.get(&handle).copied().unwrap_or_default();
+                    usage.difference(texture.get_desc().usage)
+                })
+                .unwrap_or_default();
+
+            if !lacking.is_empty() {
+                error.get_or_insert(RendererError::HistoryUsageNotSupported {
+                    name: name.clone(),
+                    usage: lacking,
+                });
+            }
+
+            if let Some(handle) = entry.current {
+                let desc = TextureDescriptor {
+                    usage: entry.usage,
+                    ..entry.desc.clone()
+                };
+                let recreate = entry
+                    .spare
+                    .as_ref()
+                    .is_some_and(|texture| !texture.get_desc().usage.contains(entry.usage));
+
+                if recreate {
+                    entry.spare = None;
+                }
+
+                currents.push((handle, desc, recreate));
+            }
+        }
+
+        for (handle, desc, recreate) in currents {
+            let resource = self.resource_mut(&handle);
+
+            match &mut resource.state {
+                VirtualResourceState::Setup(AnyFGResourceDescriptor::Texture(texture_desc)) => {
+                    texture_desc.usage |= desc.usage;
+                }
+                VirtualResourceState::Imported(_) if recreate => {
+                    resource.state = VirtualResourceState::Setup(desc.into());
+                    resource.persistent = true;
+                }
+                _ => {}
+            }
+        }
+
+        match error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        Device, FrameGraph, ResourceUsage, Texture, TextureUsages, TransientResourceCache,
+        error::RendererError,
+    };
+
+    use super::{
+        super::{
+            GpuRead, GpuWrite, ResourceRef,
+            testing::{RecordingDevice, texture_desc},
+        },
+        HistoryTexture,
+    };
+
+    #[derive(Default)]
+    struct TaaData {
+        current: Option<ResourceRef<Texture, GpuWrite>>,
+        previous: Option<ResourceRef<Texture, GpuRead>>,
+    }
+
+    ///执行结束时绑定的纹理的使用方式
+    #[derive(Debug, Default, Clone, Copy)]
+    struct Bound {
+        current: TextureUsages,
+        previous: Option<TextureUsages>,
+    }
+
+    ///taa 写入本帧的历史纹理，并以 previous_usage 读取上一帧的历史纹理。
+    ///current_usage 不为空时 copy 节点以这种方式读取本帧的历史纹理
+    fn run_frame(
+        fg: &mut FrameGraph,
+        device: &Device,
+        previous_usage: ResourceUsage,
+        current_usage: Option<ResourceUsage>,
+    ) -> Result<Bound, RendererError> {
+        fg.reset();
+
+        let HistoryTexture { current, previous } =
+            fg.create_history_texture("taa", texture_desc(64));
+        let bound = Arc::new(Mutex::new(Bound::default()));
+        let recorded = bound.clone();
+
+        let current = fg.add_callback_pass(
+            0,
+            "taa",
+            |builder, data: &mut TaaData| {
+                let current = builder.write(current);
+                builder.add_color_attachment(&current, Default::default());
+                let handle = current.handle();
+                data.current = Some(current);
+                data.previous =
+                    previous.map(|previous| builder.read_with_usage(previous, previous_usage));
+                builder.side_effect();
+
+                handle
+            },
+            move |data, render_context| {
+                let mut bound = recorded.lock().unwrap();
+                bound.current = render_context
+                    .get(data.current.as_ref().unwrap())?
+                    .get_desc()
+                    .usage;
+                if let Some(previous) = &data.previous {
+                    bound.previous = Some(render_context.get(previous)?.get_desc().usage);
+                }
+
+                Ok(())
+            },
+        );
+
+        if let Some(current_usage) = current_usage {
+            fg.add_compute_pass(
+                1,
+                "copy",
+                |builder, _: &mut ()| {
+                    builder.read_with_usage(current, current_usage);
+                    builder.side_effect();
+                },
+                |_, _| Ok(()),
+            );
+        }
+
+        fg.compile()?;
+        fg.execute(device, &mut TransientResourceCache::default())?;
+
+        let bound = *bound.lock().unwrap();
+        Ok(bound)
+    }
+
+    fn current_is_imported(fg: &FrameGraph) -> bool {
+        fg.resources()
+            .iter()
+            .find(|resource| resource.info.name == "taa")
+            .unwrap()
+            .is_imported()
+    }
+
+    #[test]
+    fn history_textures_are_bound_with_every_usage() {
+        let device = Device::new(RecordingDevice::default());
+        let mut fg = FrameGraph::default();
+        let sampled = ResourceUsage::Sampled;
+        let storage = ResourceUsage::StorageRead;
+        let attachment = TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING;
+
+        //第一帧写入的纹理在下一帧被采样
+        let bound = run_frame(&mut fg, &device, sampled, None).unwrap();
+        assert_eq!(bound.current, attachment);
+        assert_eq!(bound.previous, None);
+
+        //上一帧的纹理不能作为存储纹理，本帧不能使用
+        assert!(matches!(
+            run_frame(&mut fg, &device, storage, None),
+            Err(RendererError::HistoryUsageNotSupported { name, usage })
+                if name == "taa" && usage == TextureUsages::STORAGE_BINDING
+        ));
+
+        //重新创建的纹理包含之前所有帧的使用方式
+        let all = attachment | TextureUsages::STORAGE_BINDING;
+        let bound = run_frame(&mut fg, &device, storage, None).unwrap();
+        assert_eq!(bound.current, all);
+        assert_eq!(bound.previous, None);
+
+        let bound = run_frame(&mut fg, &device, storage, None).unwrap();
+        assert_eq!(bound.current, all);
+        assert_eq!(bound.previous, Some(all));
+
+        //复用两帧之前的纹理
+        let bound = run_frame(&mut fg, &device, storage, None).unwrap();
+        assert!(current_is_imported(&fg));
+        assert_eq!(bound.current, all);
+        assert_eq!(bound.previous, Some(all));
+
+        //复用的纹理缺少本帧新增的使用方式，重新创建，上一帧的数据仍然可用
+        let all = all | TextureUsages::COPY_SRC;
+        let bound = run_frame(&mut fg, &device, storage, Some(ResourceUsage::CopySrc)).unwrap();
+        assert!(!current_is_imported(&fg));
+        assert_eq!(bound.current, all);
+        assert!(bound.previous.is_some());
+
+        let bound = run_frame(&mut fg, &device, storage, Some(ResourceUsage::CopySrc)).unwrap();
+        assert_eq!(bound.current, all);
+        assert_eq!(bound.previous, Some(all));
+    }
+}
@@ -1,23 +1,48 @@
+pub mod aliasing;
+pub mod attachment;
+pub mod barrier;
+pub mod blackboard;
 pub mod callback_pass;
 pub mod device_pass;
+pub mod export;
 pub mod graph;
+pub mod history;
+pub mod module;
 pub mod pass;
 pub mod pass_node;
 pub mod pass_node_builder;
+pub mod plan_cache;
+pub mod queue;
+pub mod render_context;
 pub mod resource;
 pub mod resource_node;
 pub mod resource_table;
+pub mod timing;
+pub mod transfer_pass;
 pub mod transient_resource_cache;
-pub mod render_context;
 
+#[cfg(test)]
+pub(crate) mod testing;
+
+pub use aliasing::*;
+pub use attachment::*;
+pub use barrier::*;
+pub use blackboard::*;
 pub use callback_pass::*;
 pub use device_pass::*;
+pub use export::*;
 pub use graph::*;
+pub use history::*;
+pub use module::*;
 pub use pass::*;
 pub use pass_node::*;
 pub use pass_node_builder::*;
+pub use plan_cache::*;
+pub use queue::*;
+pub use render_context::*;
 pub use resource::*;
 pub use resource_node::*;
-pub use transient_resource_cache::*;
-pub use render_context::*;
 pub use resource_table::*;
+pub use timing::*;
+pub use transfer_pass::*;
+pub use transient_resource_cache::*;
@@ -0,0 +1,335 @@
+use crate::{Buffer, Texture, error::RendererError};
+
+use super::{
+    FGResource, FGResourceDescriptor, FrameGraph, PassNodeBuilder, RenderContext,
+    ResourceNodeHandle, TextureReadback, TypeEquals,
+};
+
+///打包多个渲染节点的可复用模块，例如 bloom、SSAO，同一帧中可以实例化多次
+pub trait RenderModule {
+    ///模块读取的资源节点
+    type Input;
+    ///模块写入的资源节点，交给之后的渲染节点或模块使用
+    type Output;
+
+    fn setup(&self, scope: &mut ModuleScope, input: Self::Input) -> Self::Output;
+}
+
+///模块实例中创建的资源和渲染节点名称都以实例名称为前缀
+pub struct ModuleScope<'a> {
+    fg: &'a mut FrameGraph,
+    prefix: String,
+}
+
+impl<'a> ModuleScope<'a> {
+    pub fn new(fg: &'a mut FrameGraph, prefix: &str) -> Self {
+        ModuleScope {
+            fg,
+            prefix: prefix.to_string(),
+        }
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub fn scoped_name(&self, name: &str) -> String {
+        format!("{}/{}", self.prefix, name)
+    }
+
+    ///用于导入资源、访问黑板等不需要加前缀的操作。通过 fg 添加的渲染节点和资源不会加前缀
+    pub fn fg(&mut self) -> &mut FrameGraph {
+        self.fg
+    }
+
+    pub fn create<DescriptorType>(&mut self, name: &str, desc: DescriptorType) -> ResourceNodeHandle<DescriptorType::Resource>
+    where
+        DescriptorType: FGResourceDescriptor + TypeEquals<Other = <<DescriptorType as FGResourceDescriptor>::Resource as FGResource>::Descriptor>,
+    {
+        let name = self.scoped_name(name);
+        self.fg.create(&name, desc)
+    }
+
+    pub fn add_callback_pass<Data, Output, Setup, Execute>(
+        &mut self,
+        insert_point: usize,
+        name: &str,
+        setup: Setup,
+        execute: Execute,
+    ) -> Output
+    where
+        Data: Default + Send + 'static,
+        Setup: FnOnce(&mut PassNodeBuilder, &mut Data) -> Output,
+        Execute: FnOnce(&Data, &mut RenderContext) -> Result<(), RendererError> + Send + 'static,
+    {
+        let name = self.scoped_name(name);
+        self.fg
+            .add_callback_pass(insert_point, &name, setup, execute)
+    }
+
+    pub fn add_compute_pass<Data, Output, Setup, Execute>(
+        &mut self,
+        insert_point: usize,
+        name: &str,
+        setup: Setup,
+        execute: Execute,
+    ) -> Output
+    where
+        Data: Default + Send + 'static,
+        Setup: FnOnce(&mut PassNodeBuilder, &mut Data) -> Output,
+        Execute: FnOnce(&Data, &mut RenderContext) -> Result<(), RendererError> + Send + 'static,
+    {
+        let name = self.scoped_name(name);
+        self.fg
+            .add_compute_pass(insert_point, &name, setup, execute)
+    }
+
+    pub fn add_copy_texture_pass(
+        &mut self,
+        insert_point: usize,
+        name: &str,
+        source: ResourceNodeHandle<Texture>,
+        destination: ResourceNodeHandle<Texture>,
+    ) -> ResourceNodeHandle<Texture> {
+        let name = self.scoped_name(name);
+        self.fg
+            .add_copy_texture_pass(insert_point, &name, source, destination)
+    }
+
+    pub fn add_copy_texture_to_buffer_pass(
+        &mut self,
+        insert_point: usize,
+        name: &str,
+        source: ResourceNodeHandle<Texture>,
+        destination: ResourceNodeHandle<Buffer>,
+    ) -> ResourceNodeHandle<Buffer> {
+        let name = self.scoped_name(name);
+        self.fg
+            .add_copy_texture_to_buffer_pass(insert_point, &name, source, destination)
+    }
+
+    pub fn add_copy_buffer_pass(
+        &mut self,
+        insert_point: usize,
+        name: &str,
+        source: ResourceNodeHandle<Buffer>,
+        destination: ResourceNodeHandle<Buffer>,
+    ) -> ResourceNodeHandle<Buffer> {
+        let name = self.scoped_name(name);
+        self.fg
+            .add_copy_buffer_pass(insert_point, &name, source, destination)
+    }
+
+    pub fn add_readback_pass(
+        &mut self,
+        insert_point: usize,
+        name: &str,
+        source: ResourceNodeHandle<Texture>,
+        callback: impl FnOnce(TextureReadback) + Send + 'static,
+    ) {
+        let name = self.scoped_name(name);
+        self.fg
+            .add_readback_pass(insert_point, &name, source, callback);
+    }
+
+    ///嵌套的模块实例名称为当前前缀加上实例名称
+    pub fn add_module<Module: RenderModule>(
+        &mut self,
+        instance_name: &str,
+        module: &Module,
+        input: Module::Input,
+    ) -> Module::Output {
+        let instance_name = self.scoped_name(instance_name);
+        self.fg.add_module(&instance_name, module, input)
+    }
+}
+
+impl FrameGraph {
+    ///实例名称在一帧中必须唯一，重复的实例名称在 compile 时返回错误
+    pub fn add_module<Module: RenderModule>(
+        &mut self,
+        instance_name: &str,
+        module: &Module,
+        input: Module::Input,
+    ) -> Module::Output {
+        if !self.register_module_instance(instance_name) {
+            self.push_setup_error(RendererError::DuplicateModuleInstance {
+                name: instance_name.to_string(),
+            });
+        }
+
+        let mut scope = ModuleScope::new(self, instance_name);
+        module.setup(&mut scope, input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FrameGraph, PassKind, Texture, error::RendererError};
+
+    use super::{
+        super::{
+            ResourceNodeHandle,
+            testing::{add_pass, texture_desc},
+        },
+        ModuleScope, RenderModule,
+    };
+
+    ///把 input 复制到模块内的纹理，再由计算节点写入模块的输出
+    struct Downsample;
+
+    impl RenderModule for Downsample {
+        type Input = ResourceNodeHandle<Texture>;
+        type Output = ResourceNodeHandle<Texture>;
+
+        fn setup(&self, scope: &mut ModuleScope, input: Self::Input) -> Self::Output {
+            let copy = scope.create("copy", texture_desc(64));
+            let copy = scope.add_copy_texture_pass(0, "copy", input, copy);
+            let output = scope.create("output", texture_desc(32));
+
+            scope.add_compute_pass(
+                1,
+                "filter",
+                |builder, _: &mut ()| {
+                    builder.read(copy);
+                    builder.write(output).handle()
+                },
+                |_, _| Ok(()),
+            )
+        }
+    }
+
+    ///两次嵌套的 Downsample
+    struct Bloom;
+
+    impl RenderModule for Bloom {
+        type Input = ResourceNodeHandle<Texture>;
+        type Output = ResourceNodeHandle<Texture>;
+
+        fn setup(&self, scope: &mut ModuleScope, input: Self::Input) -> Self::Output {
+            let half = scope.add_module("down0", &Downsample, input);
+            scope.add_module("down1", &Downsample, half)
+        }
+    }
+
+    ///scene 写入的纹理交给 add_modules 添加的模块，present 读取模块的输出
+    fn build_graph(
+        add_modules: impl FnOnce(
+            &mut FrameGraph,
+            ResourceNodeHandle<Texture>,
+        ) -> ResourceNodeHandle<Texture>,
+    ) -> FrameGraph {
+        let mut fg = FrameGraph::default();
+        let scene = fg.create("scene", texture_desc(64));
+        let scene = add_pass(&mut fg, 0, "scene", PassKind::Render, |builder| {
+            builder.write(scene).handle()
+        });
+
+        let output = add_modules(&mut fg, scene);
+
+        add_pass(&mut fg, 9, "present", PassKind::Render, |builder| {
+            builder.read(output);
+            builder.side_effect();
+        });
+
+        fg
+    }
+
+    fn sorted_passes(fg: &FrameGraph) -> Vec<&str> {
+        fg.sorted_pass_nodes()
+            .iter()
+            .map(|handle| fg.pass_nodes()[handle.index()].name.as_str())
+            .collect()
+    }
+
+    fn resource_names(fg: &FrameGraph) -> Vec<&str> {
+        fg.resources()
+            .iter()
+            .map(|resource| resource.info.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn nested_module_names_are_prefixed() {
+        let mut fg = build_graph(|fg, scene| fg.add_module("bloom", &Bloom, scene));
+        fg.compile().unwrap();
+
+        assert_eq!(
+            sorted_passes(&fg),
+            vec![
+                "scene",
+                "bloom/down0/copy",
+                "bloom/down0/filter",
+                "bloom/down1/copy",
+                "bloom/down1/filter",
+                "present",
+            ]
+        );
+        assert_eq!(
+            resource_names(&fg),
+            vec![
+                "scene",
+                "bloom/down0/copy",
+                "bloom/down0/output",
+                "bloom/down1/copy",
+                "bloom/down1/output",
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicate_module_instances_are_rejected() {
+        let mut fg = build_graph(|fg, scene| {
+            let output = fg.add_module("downsample", &Downsample, scene);
+            fg.add_module("downsample", &Downsample, output)
+        });
+
+        assert!(matches!(
+            fg.compile(),
+            Err(RendererError::DuplicateModuleInstance { name }) if name == "downsample"
+        ));
+
+        //reset 之后可以再次使用相同的实例名称
+        fg.reset();
+        let scene = fg.create("scene", texture_desc(64));
+        let scene = add_pass(&mut fg, 0, "scene", PassKind::Render, |builder| {
+            builder.write(scene).handle()
+        });
+        let output = fg.add_module("downsample", &Downsample, scene);
+        add_pass(&mut fg, 9, "present", PassKind::Render, |builder| {
+            builder.read(output);
+            builder.side_effect();
+        });
+        fg.compile().unwrap();
+    }
+
+    #[test]
+    fn module_outputs_are_visible_to_later_modules() {
+        let mut a_output = None;
+        let mut fg = build_graph(|fg, scene| {
+            let output = fg.add_module("a", &Downsample, scene);
+            a_output = Some(output.clone());
+            fg.add_module("b", &Downsample, output)
+        });
+        fg.compile().unwrap();
+
+        //b 读取 a 的输出，两个实例各自创建模块内的资源
+        assert_eq!(
+            sorted_passes(&fg),
+            vec![
+                "scene", "a/copy", "a/filter", "b/copy", "b/filter", "present"
+            ]
+        );
+        assert_eq!(
+            resource_names(&fg),
+            vec!["scene", "a/copy", "a/output", "b/copy", "b/output"]
+        );
+
+        let b_copy = fg
+            .pass_nodes()
+            .iter()
+            .find(|pass_node| pass_node.name == "b/copy")
+            .unwrap();
+        assert_eq!(b_copy.reads, [a_output.unwrap().resource_node_handle()]);
+    }
+}
@@ -1,9 +1,11 @@
+use crate::error::RendererError;
+
 use super::{PassNodeBuilder, render_context::RenderContext};
 
-pub trait Pass {
+pub trait Pass: Send {
     fn setup(&mut self, builder: &mut PassNodeBuilder);
 
-    fn execute(&mut self, render_context: &mut RenderContext);
+    fn execute(&mut self, render_context: &mut RenderContext) -> Result<(), RendererError>;
 }
 
 pub type DynPass = Box<dyn Pass>;
@@ -1,8 +1,13 @@
-use std::marker::PhantomData;
+use std::{collections::HashMap, marker::PhantomData};
 
-use crate::gfx_base::TypeHandle;
+use serde::Serialize;
 
-use super::{DynPass, FrameGraph, ResourceNode, ResourceNodeHandle, VirtualResource};
+use crate::{QueueType, ResourceUsage, TextureSubresourceRange, gfx_base::TypeHandle};
+
+use super::{
+    BarrierInfo, ColorAttachmentInfo, DepthStencilAttachmentInfo, DynPass, FrameGraph,
+    ResourceNode, ResourceNodeHandle, VirtualResource,
+};
 
 pub trait GpuViewType {
     const IS_WRITABLE: bool;
@@ -40,12 +45,42 @@ impl<ResourceType, ViewType> ResourceRef<ResourceType, ViewType> {
             _marker: PhantomData,
         }
     }
+
+    pub fn handle(&self) -> ResourceNodeHandle<ResourceType> {
+        self.handle.clone()
+    }
+}
+
+///渲染节点录制命令的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PassKind {
+    ///在渲染通道中录制绘制命令
+    #[default]
+    Render,
+    ///不打开渲染通道，录制计算命令
+    Compute,
+    ///只录制复制命令
+    Transfer,
+}
+
+impl PassKind {
+    pub fn supports_queue(&self, queue: QueueType) -> bool {
+        match self {
+            PassKind::Render => queue == QueueType::Graphics,
+            PassKind::Compute => queue != QueueType::Transfer,
+            PassKind::Transfer => true,
+        }
+    }
 }
 
 pub struct PassNode {
     pub insert_point: usize,
     pub name: String,
     pub handle: TypeHandle<PassNode>,
+    pub kind: PassKind,
+    ///执行渲染节点的队列，设备不支持时 compile 之后回退到图形队列
+    pub queue: QueueType,
     pub pass: Option<DynPass>,
     pub resource_request_array: Vec<TypeHandle<VirtualResource>>,
     ///使用资源的释放生命周期
@@ -53,24 +88,97 @@ pub struct PassNode {
 
     pub writes: Vec<TypeHandle<ResourceNode>>,
     pub reads: Vec<TypeHandle<ResourceNode>>,
+    ///只读取部分纹理范围的资源节点，不在其中的读取为整个资源
+    pub read_ranges: HashMap<TypeHandle<ResourceNode>, TextureSubresourceRange>,
+    ///读写资源节点的使用方式，setup 时没有指定的在 compile 时推导
+    pub usages: HashMap<TypeHandle<ResourceNode>, ResourceUsage>,
+    ///执行前需要录制的屏障
+    pub barriers: Vec<BarrierInfo>,
+
+    ///被引用的次数，为0时渲染节点会被剔除
+    pub ref_count: u32,
+    ///具有副作用的渲染节点不会被剔除
+    pub side_effect: bool,
+
+    pub color_attachments: Vec<ColorAttachmentInfo>,
+    pub depth_stencil_attachment: Option<DepthStencilAttachmentInfo>,
 }
 
 impl PassNode {
+    pub fn is_culled(&self) -> bool {
+        self.ref_count == 0 && !self.side_effect
+    }
+
+    ///按声明顺序排列的附件，包括解析目标和深度模板附件
+    pub fn attachment_handles(&self) -> Vec<TypeHandle<VirtualResource>> {
+        let mut handles = vec![];
+
+        for attachment in self.color_attachments.iter() {
+            handles.push(attachment.texture);
+            handles.extend(attachment.resolve_target);
+        }
+
+        if let Some(attachment) = &self.depth_stencil_attachment {
+            handles.push(attachment.texture);
+        }
+
+        handles
+    }
+
+    ///与 attachment_handles 对应的纹理范围，解析目标总是使用整个纹理
+    pub fn attachment_ranges(&self) -> Vec<TextureSubresourceRange> {
+        let mut ranges = vec![];
+
+        for attachment in self.color_attachments.iter() {
+            ranges.push(attachment.range);
+
+            if attachment.resolve_target.is_some() {
+                ranges.push(TextureSubresourceRange::ALL);
+            }
+        }
+
+        if let Some(attachment) = &self.depth_stencil_attachment {
+            ranges.push(attachment.range);
+        }
+
+        ranges
+    }
+
+    pub fn read_range(&self, handle: &TypeHandle<ResourceNode>) -> TextureSubresourceRange {
+        self.read_ranges
+            .get(handle)
+            .copied()
+            .unwrap_or(TextureSubresourceRange::ALL)
+    }
+
     pub fn write<ResourceType>(
         &mut self,
         graph: &mut FrameGraph,
         resource_node_handle: ResourceNodeHandle<ResourceType>,
+    ) -> ResourceRef<ResourceType, GpuWrite> {
+        self.write_subresource(graph, resource_node_handle, TextureSubresourceRange::ALL)
+    }
+
+    pub fn write_subresource<ResourceType>(
+        &mut self,
+        graph: &mut FrameGraph,
+        resource_node_handle: ResourceNodeHandle<ResourceType>,
+        range: TextureSubresourceRange,
     ) -> ResourceRef<ResourceType, GpuWrite> {
         let resource_handle = graph
-            .get_resource_node(&resource_node_handle.resource_node_handle())
+            .resource_node(&resource_node_handle.resource_node_handle())
             .resource_handle;
-        let resource = graph.get_resource_mut(&resource_handle);
+        let source_handle =
+            graph.chained_write_source(resource_node_handle.resource_node_handle(), range);
+        let resource = graph.resource_mut(&resource_handle);
         resource.info.new_version();
 
         let resource_info = resource.info.clone();
         let new_resource_node_handle = graph.create_resource_node(resource_info);
-        let new_resource_node = graph.get_resource_node_mut(&new_resource_node_handle);
+        let new_resource_node = graph.resource_node_mut(&new_resource_node_handle);
         new_resource_node.pass_node_writer_handle = Some(self.handle);
+        new_resource_node.source_handle = Some(source_handle);
+        new_resource_node.range = range;
 
         self.writes.push(new_resource_node_handle);
 
@@ -82,35 +190,57 @@ impl PassNode {
 
     pub fn read<ResourceType>(
         &mut self,
-        graph: &FrameGraph,
         resource_node_handle: ResourceNodeHandle<ResourceType>,
     ) -> ResourceRef<ResourceType, GpuRead> {
-        let resource_node_handle = resource_node_handle.resource_node_handle();
+        self.read_subresource(resource_node_handle, TextureSubresourceRange::ALL)
+    }
 
-        if !self.reads.contains(&resource_node_handle) {
-            self.reads.push(resource_node_handle);
+    ///多次读取同一个资源节点时合并读取范围
+    pub fn read_subresource<ResourceType>(
+        &mut self,
+        resource_node_handle: ResourceNodeHandle<ResourceType>,
+        range: TextureSubresourceRange,
+    ) -> ResourceRef<ResourceType, GpuRead> {
+        let handle = resource_node_handle.resource_node_handle();
+
+        if !self.reads.contains(&handle) {
+            self.reads.push(handle);
+
+            if !range.is_all() {
+                self.read_ranges.insert(handle, range);
+            }
+        } else if let Some(read_range) = self.read_ranges.get(&handle).copied() {
+            let range = read_range.union(&range);
+
+            if range.is_all() {
+                self.read_ranges.remove(&handle);
+            } else {
+                self.read_ranges.insert(handle, range);
+            }
         }
 
-        let resource_handle = graph
-            .get_resource_node(&resource_node_handle)
-            .resource_handle;
-
-        ResourceRef::new(ResourceNodeHandle::new(
-            resource_node_handle,
-            resource_handle,
-        ))
+        ResourceRef::new(resource_node_handle)
     }
 
     pub fn new(insert_point: usize, name: &str, handle: TypeHandle<PassNode>) -> Self {
         PassNode {
             name: name.to_string(),
             handle,
+            kind: PassKind::default(),
+            queue: QueueType::default(),
             pass: None,
             writes: vec![],
             reads: vec![],
+            read_ranges: HashMap::default(),
+            usages: HashMap::default(),
+            barriers: vec![],
             insert_point,
             resource_request_array: vec![],
             resource_release_array: vec![],
+            ref_count: 0,
+            side_effect: false,
+            color_attachments: vec![],
+            depth_stencil_attachment: None,
         }
     }
 }
@@ -1,8 +1,11 @@
-use crate::gfx_base::TypeHandle;
+use crate::{
+    Color, Operations, QueueType, ResourceUsage, Texture, TextureSubresourceRange,
+    error::RendererError, gfx_base::TypeHandle,
+};
 
 use super::{
-    FGResource, FGResourceDescriptor, FrameGraph, GpuRead, GpuWrite, PassNode, ResourceNodeHandle,
-    ResourceRef, TypeEquals,
+    ColorAttachmentInfo, DepthStencilAttachmentInfo, FGResource, FGResourceDescriptor, FrameGraph,
+    GpuRead, GpuWrite, PassNode, ResourceNode, ResourceNodeHandle, ResourceRef, TypeEquals,
 };
 
 pub struct PassNodeBuilder<'a> {
@@ -27,6 +30,92 @@ impl<'a> PassNodeBuilder<'a> {
         }
     }
 
+    ///标记渲染节点具有副作用，即使输出没有被读取也不会被剔除
+    pub fn side_effect(&mut self) {
+        self.pass_node.as_mut().unwrap().side_effect = true;
+    }
+
+    ///渲染节点只能在图形队列上执行，计算节点可以在图形或计算队列上执行，复制节点可以在任意队列上执行
+    pub fn set_queue(&mut self, queue: QueueType) {
+        self.pass_node.as_mut().unwrap().queue = queue;
+    }
+
+    pub fn add_color_attachment(
+        &mut self,
+        texture: &ResourceRef<Texture, GpuWrite>,
+        ops: Operations<Color>,
+    ) {
+        let range = self.attachment_range(texture);
+
+        self.pass_node
+            .as_mut()
+            .unwrap()
+            .color_attachments
+            .push(ColorAttachmentInfo {
+                texture: texture.resource_handle(),
+                range,
+                resolve_target: None,
+                ops,
+            });
+    }
+
+    ///多重采样的颜色附件，渲染结束后解析到 resolve_target
+    pub fn add_color_attachment_with_resolve(
+        &mut self,
+        texture: &ResourceRef<Texture, GpuWrite>,
+        resolve_target: &ResourceRef<Texture, GpuWrite>,
+        ops: Operations<Color>,
+    ) {
+        let range = self.attachment_range(texture);
+
+        self.pass_node
+            .as_mut()
+            .unwrap()
+            .color_attachments
+            .push(ColorAttachmentInfo {
+                texture: texture.resource_handle(),
+                range,
+                resolve_target: Some(resolve_target.resource_handle()),
+                ops,
+            });
+    }
+
+    ///depth_ops 和 stencil_ops 为空时附件只读，可以使用读取的资源
+    pub fn set_depth_stencil_attachment<ViewType>(
+        &mut self,
+        texture: &ResourceRef<Texture, ViewType>,
+        depth_ops: Option<Operations<f32>>,
+        stencil_ops: Option<Operations<u32>>,
+    ) {
+        let range = self.attachment_range(texture);
+
+        self.pass_node.as_mut().unwrap().depth_stencil_attachment =
+            Some(DepthStencilAttachmentInfo {
+                texture: texture.resource_handle(),
+                range,
+                depth_ops,
+                stencil_ops,
+            });
+    }
+
+    ///附件使用声明读写时的纹理范围
+    fn attachment_range<ViewType>(
+        &self,
+        texture: &ResourceRef<Texture, ViewType>,
+    ) -> TextureSubresourceRange {
+        let pass_node = self.pass_node.as_ref().unwrap();
+        let handle = texture.resource_node_handle();
+
+        match self.graph.resource_nodes().get(handle.index()) {
+            Some(resource_node)
+                if resource_node.pass_node_writer_handle == Some(pass_node.handle) =>
+            {
+                resource_node.range
+            }
+            _ => pass_node.read_range(&handle),
+        }
+    }
+
     pub fn create<DescriptorType>(
         &mut self,
         name: &str,
@@ -42,19 +131,123 @@ impl<'a> PassNodeBuilder<'a> {
         &mut self,
         resource_node_handle: ResourceNodeHandle<ResourceType>,
     ) -> ResourceRef<ResourceType, GpuRead> {
+        self.read_range(resource_node_handle, TextureSubresourceRange::ALL)
+    }
+
+    ///指定读取的使用方式，例如作为顶点缓冲区或间接绘制参数
+    pub fn read_with_usage<ResourceType>(
+        &mut self,
+        resource_node_handle: ResourceNodeHandle<ResourceType>,
+        usage: ResourceUsage,
+    ) -> ResourceRef<ResourceType, GpuRead> {
+        let resource_ref = self.read(resource_node_handle);
+        self.set_usage(&resource_ref.resource_node_handle(), usage);
+
+        resource_ref
+    }
+
+    pub fn write_with_usage<ResourceType>(
+        &mut self,
+        resource_node_handle: ResourceNodeHandle<ResourceType>,
+        usage: ResourceUsage,
+    ) -> ResourceRef<ResourceType, GpuWrite> {
+        let resource_ref = self.write(resource_node_handle);
+        self.set_usage(&resource_ref.resource_node_handle(), usage);
+
+        resource_ref
+    }
+
+    ///只记录已经声明读写的资源节点
+    fn set_usage(&mut self, handle: &TypeHandle<ResourceNode>, usage: ResourceUsage) {
+        let pass_node = self.pass_node.as_mut().unwrap();
+
+        if pass_node.reads.contains(handle) || pass_node.writes.contains(handle) {
+            pass_node.usages.insert(*handle, usage);
+        }
+    }
+
+    ///只读取纹理的部分 mip 层级和数组层，只和写入重叠范围的渲染节点建立依赖
+    pub fn read_subresource(
+        &mut self,
+        resource_node_handle: ResourceNodeHandle<Texture>,
+        range: TextureSubresourceRange,
+    ) -> ResourceRef<Texture, GpuRead> {
+        self.read_range(resource_node_handle, range)
+    }
+
+    pub fn write<ResourceType>(
+        &mut self,
+        resource_node_handle: ResourceNodeHandle<ResourceType>,
+    ) -> ResourceRef<ResourceType, GpuWrite> {
+        self.write_range(resource_node_handle, TextureSubresourceRange::ALL)
+    }
+
+    ///只写入纹理的部分 mip 层级和数组层，其余范围保留之前的内容
+    pub fn write_subresource(
+        &mut self,
+        resource_node_handle: ResourceNodeHandle<Texture>,
+        range: TextureSubresourceRange,
+    ) -> ResourceRef<Texture, GpuWrite> {
+        self.write_range(resource_node_handle, range)
+    }
+
+    fn read_range<ResourceType>(
+        &mut self,
+        resource_node_handle: ResourceNodeHandle<ResourceType>,
+        range: TextureSubresourceRange,
+    ) -> ResourceRef<ResourceType, GpuRead> {
+        if !self.check_handle(&resource_node_handle) {
+            return ResourceRef::new(resource_node_handle);
+        }
+
         self.pass_node
             .as_mut()
             .unwrap()
-            .read(self.graph, resource_node_handle)
+            .read_subresource(resource_node_handle, range)
     }
 
-    pub fn write<ResourceType>(
+    fn write_range<ResourceType>(
         &mut self,
         resource_node_handle: ResourceNodeHandle<ResourceType>,
+        range: TextureSubresourceRange,
     ) -> ResourceRef<ResourceType, GpuWrite> {
+        if !self.check_handle(&resource_node_handle) {
+            return ResourceRef::new(resource_node_handle);
+        }
+
+        let resource = self.graph.resource(&resource_node_handle.resource_handle());
+
+        if resource.read_only {
+            let error = RendererError::ReadOnlyResourceWrite {
+                pass: self.pass_node.as_ref().unwrap().name.clone(),
+                resource: resource.info.name.clone(),
+            };
+            self.graph.push_setup_error(error);
+
+            return ResourceRef::new(resource_node_handle);
+        }
+
         self.pass_node
             .as_mut()
             .unwrap()
-            .write(self.graph, resource_node_handle)
+            .write_subresource(self.graph, resource_node_handle, range)
+    }
+
+    ///无效的句柄不会被记录到渲染节点中，错误在 compile 时返回
+    fn check_handle<ResourceType>(
+        &mut self,
+        resource_node_handle: &ResourceNodeHandle<ResourceType>,
+    ) -> bool {
+        if self.graph.is_valid_handle(resource_node_handle) {
+            return true;
+        }
+
+        let error = RendererError::StaleResourceHandle {
+            pass: self.pass_node.as_ref().unwrap().name.clone(),
+            index: resource_node_handle.resource_node_handle().index(),
+        };
+        self.graph.push_setup_error(error);
+
+        false
     }
 }
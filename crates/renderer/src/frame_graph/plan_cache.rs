@@ -0,0 +1,271 @@
+use std::{
+    collections::{HashMap, hash_map::DefaultHasher},
+    hash::{Hash, Hasher},
+};
+
+use crate::{LoadOp, QueueType, ResourceUsage, StoreOp, gfx_base::TypeHandle};
+
+use super::{
+    AliasingPlan, AnyFGResourceDescriptor, BarrierInfo, FrameGraph, PassNode, QueueSync,
+    ResourceNode, VirtualResource, VirtualResourceState,
+};
+
+///编译结果缓存的命中统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlanCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+///compile 之后渲染节点的状态
+pub(crate) struct CompiledPassNode {
+    pub ref_count: u32,
+    pub side_effect: bool,
+    pub queue: QueueType,
+    pub usages: HashMap<TypeHandle<ResourceNode>, ResourceUsage>,
+    pub barriers: Vec<BarrierInfo>,
+    pub resource_request_array: Vec<TypeHandle<VirtualResource>>,
+    pub resource_release_array: Vec<TypeHandle<VirtualResource>>,
+}
+
+///compile 之后资源的状态
+pub(crate) struct CompiledResource {
+    ///合并使用方式之后的临时资源描述，导入资源为空
+    pub desc: Option<AnyFGResourceDescriptor>,
+    pub first_pass_node_handle: Option<TypeHandle<PassNode>>,
+    pub last_pass_node_handle: Option<TypeHandle<PassNode>>,
+    pub cross_queue: bool,
+}
+
+///编译结果对应的节点数量，哈希相同时再次确认结构相同
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PlanShape {
+    pub pass_count: usize,
+    pub resource_node_count: usize,
+    pub resource_count: usize,
+}
+
+///结构相同的帧可以直接复用的编译结果
+pub(crate) struct CompiledPlan {
+    pub shape: PlanShape,
+    pub sorted_pass_nodes: Vec<TypeHandle<PassNode>>,
+    pub pass_nodes: Vec<CompiledPassNode>,
+    pub resources: Vec<CompiledResource>,
+    pub reader_counts: Vec<u32>,
+    pub aliasing_plan: AliasingPlan,
+    ///合并进同一个 DevicePass 的渲染节点
+    pub groups: Vec<Vec<TypeHandle<PassNode>>>,
+    ///与 groups 对应的队列同步
+    pub queue_syncs: Vec<QueueSync>,
+}
+
+///保存上一帧的编译结果，reset 之后保留
+#[derive(Default)]
+pub struct PlanCache {
+    key: Option<u64>,
+    plan: Option<CompiledPlan>,
+    stats: PlanCacheStats,
+}
+
+impl PlanCache {
+    pub fn stats(&self) -> PlanCacheStats {
+        self.stats
+    }
+
+    ///丢弃缓存的编译结果，保留命中统计
+    pub fn clear(&mut self) {
+        self.key = None;
+        self.plan = None;
+    }
+
+    ///哈希和节点数量都相同时命中，取出缓存的编译结果，同时更新统计，使用之后需要重新 insert
+    pub(crate) fn take(&mut self, key: u64, shape: PlanShape) -> Option<CompiledPlan> {
+        let plan = self
+            .plan
+            .take()
+            .filter(|plan| self.key == Some(key) && plan.shape == shape);
+
+        if plan.is_some() {
+            self.stats.hits += 1;
+        } else {
+            self.stats.misses += 1;
+        }
+
+        plan
+    }
+
+    pub(crate) fn insert(&mut self, key: u64, plan: CompiledPlan) {
+        self.key = Some(key);
+        self.plan = Some(plan);
+    }
+}
+
+impl FrameGraph {
+    pub(crate) fn plan_shape(&self) -> PlanShape {
+        PlanShape {
+            pass_count: self.pass_nodes().len(),
+            resource_node_count: self.resource_nodes().len(),
+            resource_count: self.resources().len(),
+        }
+    }
+
+    ///只包含影响编译结果的声明，不包含导入的具体资源和清除颜色等执行时的数据
+    pub(crate) fn structure_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        self.compile_options().merge_passes.hash(&mut hasher);
+        self.compile_options().queues.hash(&mut hasher);
+
+        self.resources().len().hash(&mut hasher);
+        for resource in self.resources() {
+            resource.info.name.hash(&mut hasher);
+            resource.persistent.hash(&mut hasher);
+            resource.read_only.hash(&mut hasher);
+
+            match &resource.state {
+                VirtualResourceState::Setup(desc) => {
+                    false.hash(&mut hasher);
+                    desc.hash(&mut hasher);
+                }
+                VirtualResourceState::Imported(state) => {
+                    true.hash(&mut hasher);
+                    state.desc.hash(&mut hasher);
+                }
+            }
+        }
+
+        self.resource_nodes().len().hash(&mut hasher);
+        for resource_node in self.resource_nodes() {
+            resource_node.resource_handle.hash(&mut hasher);
+            resource_node.version.hash(&mut hasher);
+            resource_node.pass_node_writer_handle.hash(&mut hasher);
+            resource_node.source_handle.hash(&mut hasher);
+            resource_node.range.hash(&mut hasher);
+        }
+
+        self.pass_nodes().len().hash(&mut hasher);
+        for pass_node in self.pass_nodes() {
+            Self::hash_pass_node(pass_node, &mut hasher);
+        }
+
+        hasher.finish()
+    }
+
+    fn hash_pass_node(pass_node: &PassNode, hasher: &mut DefaultHasher) {
+        pass_node.insert_point.hash(hasher);
+        pass_node.name.hash(hasher);
+        pass_node.kind.hash(hasher);
+        pass_node.queue.hash(hasher);
+        pass_node.side_effect.hash(hasher);
+
+        pass_node.reads.len().hash(hasher);
+        for handle in pass_node.reads.iter() {
+            handle.hash(hasher);
+            pass_node.read_range(handle).hash(hasher);
+            pass_node.usages.get(handle).hash(hasher);
+        }
+
+        pass_node.writes.len().hash(hasher);
+        for handle in pass_node.writes.iter() {
+            handle.hash(hasher);
+            pass_node.usages.get(handle).hash(hasher);
+        }
+
+        pass_node.color_attachments.len().hash(hasher);
+        for attachment in pass_node.color_attachments.iter() {
+            attachment.texture.hash(hasher);
+            attachment.range.hash(hasher);
+            attachment.resolve_target.hash(hasher);
+            matches!(attachment.ops.load, LoadOp::Load).hash(hasher);
+            (attachment.ops.store == StoreOp::Store).hash(hasher);
+        }
+
+        pass_node.depth_stencil_attachment.is_some().hash(hasher);
+        if let Some(attachment) = &pass_node.depth_stencil_attachment {
+            attachment.texture.hash(hasher);
+            attachment.range.hash(hasher);
+
+            for ops in [
+                attachment
+                    .depth_ops
+                    .map(|ops| (matches!(ops.load, LoadOp::Load), ops.store)),
+                attachment
+                    .stencil_ops
+                    .map(|ops| (matches!(ops.load, LoadOp::Load), ops.store)),
+            ] {
+                ops.map(|(load, store)| (load, store == StoreOp::Store))
+                    .hash(hasher);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Device, FrameGraph, PassKind, TransientResourceCache};
+
+    use super::{
+        super::testing::{RecordingDevice, add_pass, texture_desc},
+        PlanCacheStats, PlanShape,
+    };
+
+    ///gbuffer -> lighting -> present，with_bloom 为 true 时最后的渲染节点为 bloom
+    fn build_frame(fg: &mut FrameGraph, with_bloom: bool) {
+        fg.reset();
+
+        let color = fg.create("color", texture_desc(64));
+        let color = add_pass(fg, 0, "gbuffer", PassKind::Render, |builder| {
+            builder.write(color).handle()
+        });
+
+        let color = add_pass(fg, 1, "lighting", PassKind::Render, |builder| {
+            builder.read(color.clone());
+            builder.write(color).handle()
+        });
+
+        //只改变节点名称也会改变结构
+        let name = if with_bloom { "bloom" } else { "present" };
+        add_pass(fg, 2, name, PassKind::Render, |builder| {
+            builder.read(color);
+            builder.side_effect();
+        });
+    }
+
+    #[test]
+    fn same_structure_hits_and_changed_structure_misses() {
+        let device = Device::new(RecordingDevice::default());
+        let mut transient_resource_cache = TransientResourceCache::default();
+        let mut fg = FrameGraph::default();
+
+        for with_bloom in [false, false, true, true, false] {
+            build_frame(&mut fg, with_bloom);
+            fg.compile().unwrap();
+            fg.execute(&device, &mut transient_resource_cache).unwrap();
+        }
+
+        assert_eq!(
+            fg.plan_cache().stats(),
+            PlanCacheStats { hits: 2, misses: 3 }
+        );
+    }
+
+    #[test]
+    fn different_node_counts_miss_with_the_same_key() {
+        let mut fg = FrameGraph::default();
+        build_frame(&mut fg, false);
+        fg.compile().unwrap();
+
+        let key = fg.structure_hash();
+        let shape = fg.plan_shape();
+        let other = PlanShape {
+            resource_node_count: shape.resource_node_count + 1,
+            ..shape
+        };
+
+        assert!(fg.plan_cache_mut().take(key, other).is_none());
+        assert_eq!(
+            fg.plan_cache().stats(),
+            PlanCacheStats { hits: 0, misses: 2 }
+        );
+    }
+}
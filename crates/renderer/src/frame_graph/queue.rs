@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+
+use crate::{QueueType, SyncPoint, gfx_base::TypeHandle};
+
+use super::{FrameGraph, PassNode, VirtualResource};
+
+///DevicePass 提交时与其他队列的同步
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueueSync {
+    pub queue: QueueType,
+    ///执行之前需要等待的其他队列的同步点，每个队列只保留最大的值
+    pub waits: Vec<SyncPoint>,
+    ///之后有其他队列上的 DevicePass 依赖此 DevicePass 时发出的信号
+    pub signal: Option<SyncPoint>,
+}
+
+impl QueueSync {
+    ///在图形队列上执行，并且不需要同步
+    pub fn is_serial(&self) -> bool {
+        self.queue == QueueType::Graphics && self.waits.is_empty() && self.signal.is_none()
+    }
+}
+
+///资源最后一次写入的 DevicePass 和之后读取的 DevicePass
+#[derive(Default)]
+struct ResourceAccesses {
+    writer: Option<usize>,
+    readers: Vec<usize>,
+}
+
+impl FrameGraph {
+    ///按执行顺序找到不同队列的 DevicePass 之间的读写依赖，被依赖的 DevicePass 发出信号，
+    ///依赖的 DevicePass 等待信号。同一队列上的依赖由提交顺序保证
+    pub(crate) fn compute_queue_syncs(
+        &self,
+        groups: &[Vec<TypeHandle<PassNode>>],
+    ) -> Vec<QueueSync> {
+        let queues = groups
+            .iter()
+            .map(|group| self.pass_node(&group[0]).queue)
+            .collect::<Vec<_>>();
+
+        let mut accesses: HashMap<TypeHandle<VirtualResource>, ResourceAccesses> =
+            HashMap::default();
+        let mut dependencies: Vec<Vec<usize>> = vec![vec![]; groups.len()];
+
+        for (index, group) in groups.iter().enumerate() {
+            for pass_node_handle in group.iter() {
+                let pass_node = self.pass_node(pass_node_handle);
+
+                for resource_node_handle in pass_node.reads.iter() {
+                    let resource_handle = self.resource_node(resource_node_handle).resource_handle;
+                    let resource_accesses = accesses.entry(resource_handle).or_default();
+
+                    dependencies[index].extend(resource_accesses.writer);
+                    resource_accesses.readers.push(index);
+                }
+
+                for resource_node_handle in pass_node.writes.iter() {
+                    let resource_handle = self.resource_node(resource_node_handle).resource_handle;
+                    let resource_accesses = accesses.entry(resource_handle).or_default();
+
+                    dependencies[index].extend(resource_accesses.writer);
+                    dependencies[index].append(&mut resource_accesses.readers);
+                    resource_accesses.writer = Some(index);
+                }
+            }
+
+            dependencies[index].retain(|dependency| queues[*dependency] != queues[index]);
+        }
+
+        let mut signaled = vec![false; groups.len()];
+        for dependency in dependencies.iter().flatten() {
+            signaled[*dependency] = true;
+        }
+
+        //每个队列的信号值按提交顺序递增
+        let mut values: HashMap<QueueType, u64> = HashMap::default();
+        let signals = signaled
+            .iter()
+            .zip(queues.iter())
+            .map(|(signaled, queue)| {
+                signaled.then(|| {
+                    let value = values.entry(*queue).or_default();
+                    *value += 1;
+
+                    SyncPoint {
+                        queue: *queue,
+                        value: *value,
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        //同一队列上已经等待过的同步点不再重复等待
+        let mut waited: HashMap<(QueueType, QueueType), u64> = HashMap::default();
+
+        queues
+            .iter()
+            .zip(dependencies.iter())
+            .zip(signals.iter())
+            .map(|((queue, dependencies), signal)| {
+                let mut waits: Vec<SyncPoint> = vec![];
+
+                for wait in dependencies
+                    .iter()
+                    .filter_map(|dependency| signals[*dependency])
+                {
+                    let value = waited.entry((*queue, wait.queue)).or_default();
+                    if wait.value <= *value {
+                        continue;
+                    }
+                    *value = wait.value;
+
+                    match waits.iter_mut().find(|other| other.queue == wait.queue) {
+                        Some(other) => other.value = wait.value,
+                        None => waits.push(wait),
+                    }
+                }
+
+                waits.sort_by_key(|wait| wait.queue);
+
+                QueueSync {
+                    queue: *queue,
+                    waits,
+                    signal: *signal,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        CompileOptions, Device, FrameGraph, PassKind, QueueType, SyncPoint, TransientResourceCache,
+        error::RendererError,
+    };
+
+    use super::super::testing::{RecordingDevice, Submitted, add_pass, buffer_desc};
+
+    ///gbuffer -> ssao(计算队列) -> lighting，shadow 与 ssao 没有依赖
+    fn build_graph(fg: &mut FrameGraph, ssao_queue: QueueType) {
+        let depth = fg.create("depth", buffer_desc());
+        let ao = fg.create("ao", buffer_desc());
+        let shadow = fg.create("shadow", buffer_desc());
+
+        let depth = add_pass(fg, 0, "gbuffer", PassKind::Render, |builder| {
+            builder.write(depth).handle()
+        });
+
+        let ao = add_pass(fg, 1, "ssao", PassKind::Compute, |builder| {
+            builder.set_queue(ssao_queue);
+            builder.read(depth);
+            builder.write(ao).handle()
+        });
+
+        let shadow = add_pass(fg, 2, "shadow", PassKind::Render, |builder| {
+            builder.write(shadow).handle()
+        });
+
+        add_pass(fg, 3, "lighting", PassKind::Render, |builder| {
+            builder.read(ao);
+            builder.read(shadow);
+            builder.side_effect();
+        });
+    }
+
+    fn execute(queues: Vec<QueueType>) -> (FrameGraph, Vec<Submitted>) {
+        let recording = RecordingDevice::with_queues(queues);
+        let device = Device::new(recording.clone());
+        let mut transient_resource_cache = TransientResourceCache::default();
+
+        let mut fg = FrameGraph::default();
+        fg.set_compile_options(CompileOptions {
+            queues: device.queues(),
+            ..Default::default()
+        });
+        build_graph(&mut fg, QueueType::Compute);
+
+        fg.compile().unwrap();
+        fg.execute(&device, &mut transient_resource_cache).unwrap();
+
+        (fg, recording.take_submitted())
+    }
+
+    #[test]
+    fn async_compute_sync_points() {
+        let (fg, submitted) = execute(vec![QueueType::Graphics, QueueType::Compute]);
+
+        let graphics = |value| SyncPoint {
+            queue: QueueType::Graphics,
+            value,
+        };
+        let compute = |value| SyncPoint {
+            queue: QueueType::Compute,
+            value,
+        };
+
+        assert_eq!(
+            submitted,
+            vec![
+                Submitted::Queue {
+                    queue: QueueType::Graphics,
+                    waits: vec![],
+                    command_buffers: 1,
+                    signal: Some(graphics(1)),
+                },
+                Submitted::Queue {
+                    queue: QueueType::Compute,
+                    waits: vec![graphics(1)],
+                    command_buffers: 1,
+                    signal: Some(compute(1)),
+                },
+                Submitted::Queue {
+                    queue: QueueType::Graphics,
+                    waits: vec![],
+                    command_buffers: 1,
+                    signal: None,
+                },
+                Submitted::Queue {
+                    queue: QueueType::Graphics,
+                    waits: vec![compute(1)],
+                    command_buffers: 1,
+                    signal: None,
+                },
+            ]
+        );
+
+        //计算队列使用的资源不参与内存共享
+        let cross_queue = fg
+            .resources()
+            .iter()
+            .map(|resource| (resource.info.name.as_str(), resource.cross_queue))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            cross_queue,
+            vec![("depth", true), ("ao", true), ("shadow", false)]
+        );
+    }
+
+    #[test]
+    fn single_queue_collapses_to_serial_submit() {
+        let (fg, submitted) = execute(vec![QueueType::Graphics]);
+
+        assert_eq!(submitted, vec![Submitted::Serial(4)]);
+        assert!(
+            fg.pass_nodes()
+                .iter()
+                .all(|pass_node| pass_node.queue == QueueType::Graphics)
+        );
+        assert!(fg.resources().iter().all(|resource| !resource.cross_queue));
+    }
+
+    #[test]
+    fn render_pass_on_compute_queue_is_rejected() {
+        let mut fg = FrameGraph::default();
+        let target = fg.create("target", buffer_desc());
+
+        add_pass(&mut fg, 0, "draw", PassKind::Render, |builder| {
+            builder.set_queue(QueueType::Compute);
+            builder.write(target);
+            builder.side_effect();
+        });
+
+        assert!(matches!(
+            fg.compile(),
+            Err(RendererError::UnsupportedQueue {
+                queue: QueueType::Compute,
+                ..
+            })
+        ));
+    }
+}
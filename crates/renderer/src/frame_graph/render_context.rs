@@ -1,12 +1,66 @@
-use crate::{CommandBuffer, Device};
+use std::time::Duration;
 
-use super::{ResourceTable, TransientResourceCache};
+use crate::{
+    BarrierResource, Buffer, BufferDescriptor, BufferUsages, CommandBuffer, Device,
+    QueueSubmission, ResourceBarrier, TexelCopyBufferLayout, Texture, TypeHandle,
+    error::RendererError,
+};
+
+use super::{
+    AnyFGResource, BarrierInfo, FGResource, FrameTimingReport, GpuRead, GpuWrite, PassTiming,
+    PendingReadback, QueueSync, ReadbackFn, ResourceRef, ResourceRequest, ResourceTable,
+    TransientResourceCache, VirtualResource,
+};
+
+///渲染节点访问资源的方式
+enum ContextResources<'a> {
+    ///串行执行时按渲染节点申请和释放资源
+    Owned {
+        resource_table: ResourceTable,
+        transient_resource_cache: &'a mut TransientResourceCache,
+    },
+    ///并行录制时资源提前申请，DevicePass 写入的资源由录制的线程独占，其余资源共享只读
+    Shared {
+        owned: ResourceTable,
+        shared: &'a ResourceTable,
+    },
+}
+
+impl ContextResources<'_> {
+    fn get_resource(&self, handle: &TypeHandle<VirtualResource>) -> Option<&AnyFGResource> {
+        match self {
+            ContextResources::Owned { resource_table, .. } => resource_table.get_resource(handle),
+            ContextResources::Shared { owned, shared } => owned
+                .get_resource(handle)
+                .or_else(|| shared.get_resource(handle)),
+        }
+    }
+}
+
+///并行录制时一个 DevicePass 录制的结果
+pub(crate) struct RecordedCommands {
+    command_buffers: Vec<(QueueSync, CommandBuffer)>,
+    readbacks: Vec<PendingReadback>,
+    pass_timings: Vec<PassTiming>,
+}
 
 pub struct RenderContext<'a> {
     device: &'a Device,
     cb: Option<CommandBuffer>,
-    pub(crate) resource_table: ResourceTable,
-    pub(crate) transient_resource_cache: &'a mut TransientResourceCache,
+    ///本帧录制完成等待提交的命令和 DevicePass 的队列同步
+    command_buffers: Vec<(QueueSync, CommandBuffer)>,
+    ///提交之后需要读取的缓冲区
+    readbacks: Vec<PendingReadback>,
+    ///已经执行的渲染节点的执行时间
+    timing_report: FrameTimingReport,
+    ///设备是否支持时间戳查询
+    timestamps: bool,
+    resources: &'a [VirtualResource],
+    ///当前执行的渲染节点名称
+    pass_name: String,
+    ///当前执行的渲染节点声明读写的资源
+    pass_resource_handles: Vec<TypeHandle<VirtualResource>>,
+    resource_table: ContextResources<'a>,
 }
 
 impl<'a> RenderContext<'a> {
@@ -14,20 +68,512 @@ impl<'a> RenderContext<'a> {
         self.device
     }
 
+    pub fn get<ResourceType: FGResource, ViewType>(
+        &self,
+        resource_ref: &ResourceRef<ResourceType, ViewType>,
+    ) -> Result<&ResourceType, RendererError> {
+        let handle = resource_ref.resource_handle();
+        self.check_declared(&handle)?;
+
+        self.get_resource(&handle)
+    }
+
+    ///不检查当前渲染节点是否声明了此资源
+    pub(crate) fn get_resource<ResourceType: FGResource>(
+        &self,
+        handle: &TypeHandle<VirtualResource>,
+    ) -> Result<&ResourceType, RendererError> {
+        let resource = self.resource_table.get_resource(handle).ok_or_else(|| {
+            RendererError::ResourceNotAvailable {
+                pass: self.pass_name.clone(),
+                resource: self.resource_name(handle),
+            }
+        })?;
+
+        ResourceType::borrow_resource(resource).ok_or_else(|| RendererError::ResourceTypeMismatch {
+            pass: self.pass_name.clone(),
+            resource: self.resource_name(handle),
+        })
+    }
+
+    pub fn get_mut<ResourceType: FGResource>(
+        &mut self,
+        resource_ref: &ResourceRef<ResourceType, GpuWrite>,
+    ) -> Result<&mut ResourceType, RendererError> {
+        let handle = resource_ref.resource_handle();
+        self.get(resource_ref)?;
+
+        let pass = self.pass_name.clone();
+        let resource = self.resource_name(&handle);
+
+        let resource_table = match &mut self.resource_table {
+            ContextResources::Owned { resource_table, .. } => resource_table,
+            ContextResources::Shared { owned, .. } => owned,
+        };
+
+        //并行录制时只有当前 DevicePass 写入的资源在 owned 中
+        let Some(any_resource) = resource_table.get_resource_mut(&handle) else {
+            return Err(RendererError::SharedResourceNotMutable { pass, resource });
+        };
+
+        ResourceType::borrow_resource_mut(any_resource)
+            .ok_or(RendererError::ImportedResourceNotMutable { pass, resource })
+    }
+
+    fn check_declared(&self, handle: &TypeHandle<VirtualResource>) -> Result<(), RendererError> {
+        if self.pass_resource_handles.contains(handle) {
+            Ok(())
+        } else {
+            Err(RendererError::UndeclaredResourceAccess {
+                pass: self.pass_name.clone(),
+                resource: self.resource_name(handle),
+            })
+        }
+    }
+
+    fn resource_name(&self, handle: &TypeHandle<VirtualResource>) -> String {
+        self.resources
+            .get(handle.index())
+            .map(|resource| resource.info.name.clone())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn set_pass(
+        &mut self,
+        pass_name: &str,
+        pass_resource_handles: &[TypeHandle<VirtualResource>],
+    ) {
+        self.pass_name = pass_name.to_string();
+        self.pass_resource_handles = pass_resource_handles.to_vec();
+    }
+
+    ///并行录制时资源在批次开始之前申请，不需要再申请
+    pub(crate) fn request_resource(&mut self, request: &ResourceRequest) {
+        if let ContextResources::Owned {
+            resource_table,
+            transient_resource_cache,
+        } = &mut self.resource_table
+        {
+            resource_table.request_resources(request, self.device, transient_resource_cache);
+        }
+    }
+
+    ///并行录制时资源在批次结束之后释放
+    pub(crate) fn release_resource(&mut self, handle: &TypeHandle<VirtualResource>) {
+        if let ContextResources::Owned {
+            resource_table,
+            transient_resource_cache,
+        } = &mut self.resource_table
+        {
+            resource_table.release_resource(handle, transient_resource_cache);
+        }
+    }
+
+    pub(crate) fn take_resource_table(&mut self) -> ResourceTable {
+        match &mut self.resource_table {
+            ContextResources::Owned { resource_table, .. } => std::mem::take(resource_table),
+            ContextResources::Shared { owned, .. } => std::mem::take(owned),
+        }
+    }
+
+    ///复制整个纹理，两个纹理的大小必须相同
+    pub fn copy_texture_to_texture(
+        &mut self,
+        source: &ResourceRef<Texture, GpuRead>,
+        destination: &ResourceRef<Texture, GpuWrite>,
+    ) -> Result<(), RendererError> {
+        self.record(|render_context, cb| {
+            let source_texture = render_context.get(source)?;
+            let destination_texture = render_context.get(destination)?;
+
+            let from_size = source_texture.get_desc().size;
+            let to_size = destination_texture.get_desc().size;
+            if from_size != to_size {
+                return Err(RendererError::CopySizeMismatch {
+                    pass: render_context.pass_name.clone(),
+                    from: render_context.resource_name(&source.resource_handle()),
+                    from_size,
+                    to: render_context.resource_name(&destination.resource_handle()),
+                    to_size,
+                });
+            }
+
+            cb.copy_texture_to_texture(source_texture, destination_texture, from_size);
+
+            Ok(())
+        })
+    }
+
+    ///复制整个纹理到缓冲区，每行填充到 COPY_BYTES_PER_ROW_ALIGNMENT 的倍数
+    pub fn copy_texture_to_buffer(
+        &mut self,
+        source: &ResourceRef<Texture, GpuRead>,
+        destination: &ResourceRef<Buffer, GpuWrite>,
+    ) -> Result<(), RendererError> {
+        self.record(|render_context, cb| {
+            let source = render_context.get(source)?;
+            let destination = render_context.get(destination)?;
+            let layout = TexelCopyBufferLayout::aligned(source.get_desc());
+
+            cb.copy_texture_to_buffer(source, destination, layout, source.get_desc().size);
+
+            Ok(())
+        })
+    }
+
+    ///复制两个缓冲区中较小的长度
+    pub fn copy_buffer_to_buffer(
+        &mut self,
+        source: &ResourceRef<Buffer, GpuRead>,
+        destination: &ResourceRef<Buffer, GpuWrite>,
+    ) -> Result<(), RendererError> {
+        self.record(|render_context, cb| {
+            let source = render_context.get(source)?;
+            let destination = render_context.get(destination)?;
+            let size = source.get_desc().size.min(destination.get_desc().size);
+
+            cb.copy_buffer_to_buffer(source, 0, destination, 0, size);
+
+            Ok(())
+        })
+    }
+
+    ///复制纹理到 CPU 可读的缓冲区，命令提交并执行完成后调用 callback，
+    ///callback 收到的数据为紧密排列
+    pub fn readback_texture(
+        &mut self,
+        source: &ResourceRef<Texture, GpuRead>,
+        callback: ReadbackFn,
+    ) -> Result<(), RendererError> {
+        let device = self.device;
+
+        let readback = self.record(|render_context, cb| {
+            let texture = render_context.get(source)?;
+            let desc = texture.get_desc();
+            let layout = TexelCopyBufferLayout::aligned(desc);
+
+            //暂存缓冲区不经过 TransientResourceCache，避免在读取之前被之后的渲染节点复用
+            let buffer = device.create_buffer_with_label(
+                BufferDescriptor {
+                    size: layout.size_in_bytes(desc.size),
+                    usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+                },
+                &format!("{}/readback", render_context.pass_name),
+            );
+
+            cb.copy_texture_to_buffer(texture, &buffer, layout, desc.size);
+
+            Ok(PendingReadback {
+                buffer,
+                size: desc.size,
+                format: desc.format,
+                layout,
+                callback,
+            })
+        })?;
+
+        self.readbacks.push(readback);
+
+        Ok(())
+    }
+
+    fn record<Output>(
+        &mut self,
+        record: impl FnOnce(&Self, &mut CommandBuffer) -> Result<Output, RendererError>,
+    ) -> Result<Output, RendererError> {
+        let mut cb = self
+            .cb
+            .take()
+            .ok_or_else(|| RendererError::CommandBufferNotAvailable {
+                pass: self.pass_name.clone(),
+            })?;
+
+        let output = record(self, &mut cb);
+        self.cb = Some(cb);
+
+        output
+    }
+
+    ///资源还没有申请时返回 None
+    pub(crate) fn create_barrier(&self, barrier: &BarrierInfo) -> Option<ResourceBarrier<'_>> {
+        let resource = self.resource_table.get_resource(&barrier.resource)?;
+
+        let resource = match Texture::borrow_resource(resource) {
+            Some(texture) => BarrierResource::Texture {
+                texture,
+                range: barrier.range,
+            },
+            None => BarrierResource::Buffer(Buffer::borrow_resource(resource)?),
+        };
+
+        Some(ResourceBarrier {
+            resource,
+            before: barrier.before,
+            after: barrier.after,
+        })
+    }
+
+    pub(crate) fn push_command_buffer(&mut self, queue_sync: QueueSync, cb: CommandBuffer) {
+        self.command_buffers.push((queue_sync, cb));
+    }
+
+    ///在 GPU 调试工具中标记当前命令的位置
+    pub fn insert_debug_marker(&mut self, label: &str) {
+        if let Some(cb) = self.cb.as_mut() {
+            cb.insert_debug_marker(label);
+        }
+    }
+
+    ///渲染节点的命令以渲染节点名称分组
+    pub(crate) fn push_debug_group(&mut self, label: &str) {
+        if let Some(cb) = self.cb.as_mut() {
+            cb.push_debug_group(label);
+        }
+    }
+
+    pub(crate) fn pop_debug_group(&mut self) {
+        if let Some(cb) = self.cb.as_mut() {
+            cb.pop_debug_group();
+        }
+    }
+
+    ///设备支持时间戳查询时在当前命令缓冲区中写入时间戳
+    pub(crate) fn write_timestamp(&mut self, index: u32) {
+        if !self.timestamps {
+            return;
+        }
+
+        if let Some(cb) = self.cb.as_mut() {
+            cb.write_timestamp(index);
+        }
+    }
+
+    ///timestamp_index 为渲染节点开始时写入的时间戳
+    pub(crate) fn push_pass_timing(
+        &mut self,
+        name: &str,
+        cpu_time: Duration,
+        timestamp_index: u32,
+    ) {
+        let timestamp_index = self.timestamps.then_some(timestamp_index);
+
+        self.timing_report
+            .passes
+            .push(PassTiming::new(name, cpu_time, timestamp_index));
+    }
+
+    ///取出录制完成的命令和回读，交给提交的 RenderContext
+    pub(crate) fn take_recorded(&mut self) -> RecordedCommands {
+        RecordedCommands {
+            command_buffers: std::mem::take(&mut self.command_buffers),
+            readbacks: std::mem::take(&mut self.readbacks),
+            pass_timings: std::mem::take(&mut self.timing_report.passes),
+        }
+    }
+
+    pub(crate) fn append_recorded(&mut self, recorded: RecordedCommands) {
+        self.command_buffers.extend(recorded.command_buffers);
+        self.readbacks.extend(recorded.readbacks);
+        self.timing_report.passes.extend(recorded.pass_timings);
+    }
+
+    ///submit 之后取出本帧的执行时间
+    pub(crate) fn take_timing_report(&mut self) -> FrameTimingReport {
+        std::mem::take(&mut self.timing_report)
+    }
+
+    ///提交本帧录制的命令，回读的数据在命令执行完成后通过 DeviceTrait::read_buffer_async 交付，
+    ///GPU 执行时间需要等待执行完成
+    pub(crate) fn submit(&mut self) {
+        let command_buffers = std::mem::take(&mut self.command_buffers);
+
+        if command_buffers
+            .iter()
+            .all(|(queue_sync, _)| queue_sync.is_serial())
+        {
+            self.device
+                .submit(command_buffers.into_iter().map(|(_, cb)| cb).collect());
+        } else {
+            for submission in Self::queue_submissions(command_buffers) {
+                self.device.submit_queue(submission);
+            }
+        }
+
+        for readback in self.readbacks.drain(..) {
+            readback.read(self.device);
+        }
+
+        let timestamp_count = self.timing_report.timestamp_count();
+        if timestamp_count > 0 {
+            let timestamps = self.device.read_timestamps(timestamp_count);
+            self.timing_report.resolve_gpu_times(&timestamps);
+        }
+    }
+
+    ///同一队列上连续的命令合并为一次提交，需要等待时开始新的提交，发出信号时结束提交
+    fn queue_submissions(command_buffers: Vec<(QueueSync, CommandBuffer)>) -> Vec<QueueSubmission> {
+        let mut submissions: Vec<QueueSubmission> = vec![];
+
+        for (queue_sync, cb) in command_buffers {
+            let append = submissions.last().is_some_and(|submission| {
+                submission.queue == queue_sync.queue
+                    && submission.signal.is_none()
+                    && queue_sync.waits.is_empty()
+            });
+
+            if !append {
+                submissions.push(QueueSubmission {
+                    queue: queue_sync.queue,
+                    waits: queue_sync.waits,
+                    command_buffers: vec![],
+                    signal: None,
+                });
+            }
+
+            let submission = submissions.last_mut().unwrap();
+            submission.command_buffers.push(cb);
+            submission.signal = queue_sync.signal;
+        }
+
+        submissions
+    }
+
     pub fn set_cb(&mut self, cb: CommandBuffer) {
         self.cb = Some(cb);
     }
 
+    pub(crate) fn next_subpass(&mut self) {
+        if let Some(cb) = self.cb.as_mut() {
+            cb.next_subpass();
+        }
+    }
+
     pub fn take_cb(&mut self) -> Option<CommandBuffer> {
         self.cb.take()
     }
 
-    pub fn new(device: &'a Device, transient_resource_cache: &'a mut TransientResourceCache) -> Self {
+    pub fn new(
+        device: &'a Device,
+        resources: &'a [VirtualResource],
+        transient_resource_cache: &'a mut TransientResourceCache,
+    ) -> Self {
+        Self::with_resources(
+            device,
+            resources,
+            ContextResources::Owned {
+                resource_table: Default::default(),
+                transient_resource_cache,
+            },
+        )
+    }
+
+    ///并行录制时使用，owned 为 DevicePass 写入的资源，shared 中包含其余已经申请的资源
+    pub(crate) fn shared(
+        device: &'a Device,
+        resources: &'a [VirtualResource],
+        owned: ResourceTable,
+        shared: &'a ResourceTable,
+    ) -> Self {
+        Self::with_resources(
+            device,
+            resources,
+            ContextResources::Shared { owned, shared },
+        )
+    }
+
+    fn with_resources(
+        device: &'a Device,
+        resources: &'a [VirtualResource],
+        resource_table: ContextResources<'a>,
+    ) -> Self {
         Self {
             device,
             cb: None,
-            resource_table: Default::default(),
-            transient_resource_cache
+            command_buffers: vec![],
+            readbacks: vec![],
+            timing_report: FrameTimingReport::default(),
+            timestamps: device.supports_timestamps(),
+            resources,
+            pass_name: String::default(),
+            pass_resource_handles: vec![],
+            resource_table,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{Device, FrameGraph, PassKind, Texture, TransientResourceCache};
+
+    use super::super::{
+        GpuWrite, ResourceRef,
+        testing::{RecordingDevice, add_pass, texture_desc},
+    };
+    use super::RendererError;
+
+    #[derive(Default)]
+    struct PeekData {
+        color: Option<ResourceRef<Texture, GpuWrite>>,
+    }
+
+    #[test]
+    fn undeclared_resources_are_rejected() {
+        let mut fg = FrameGraph::default();
+        let color = fg.create("color", texture_desc(64));
+
+        let color = add_pass(&mut fg, 0, "draw", PassKind::Render, |builder| {
+            let color = builder.write(color);
+            builder.add_color_attachment(&color, Default::default());
+            color.handle()
+        });
+
+        let errors = Arc::new(Mutex::new(vec![]));
+        let recorded = errors.clone();
+        let peeked = color.clone();
+        fg.add_compute_pass(
+            1,
+            "peek",
+            move |builder, data: &mut PeekData| {
+                //只保存资源的引用，没有声明读写
+                data.color = Some(ResourceRef::new(peeked));
+                builder.side_effect();
+            },
+            move |data, render_context| {
+                let color = data.color.as_ref().unwrap();
+                let mut errors = recorded.lock().unwrap();
+                errors.push(render_context.get(color).err());
+                errors.push(render_context.get_mut(color).err());
+
+                Ok(())
+            },
+        );
+
+        //color 在 peek 执行时仍然可用
+        add_pass(&mut fg, 2, "present", PassKind::Compute, |builder| {
+            builder.read(color);
+            builder.side_effect();
+        });
+
+        fg.compile().unwrap();
+        fg.execute(
+            &Device::new(RecordingDevice::default()),
+            &mut TransientResourceCache::default(),
+        )
+        .unwrap();
+
+        let errors = errors.lock().unwrap();
+        assert_eq!(errors.len(), 2);
+        for error in errors.iter() {
+            assert!(
+                matches!(
+                    error,
+                    Some(RendererError::UndeclaredResourceAccess { pass, resource })
+                        if pass == "peek" && resource == "color"
+                ),
+                "{error:?}"
+            );
         }
     }
 }
@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use crate::{Buffer, BufferDescriptor};
+
+use super::{
+    AnyFGResource, AnyFGResourceDescriptor, FGResource, FGResourceDescriptor,
+    ImportedVirtualResource, ImportedVirtualResourceState,
+};
+
+impl FGResource for Buffer {
+    type Descriptor = BufferDescriptor;
+
+    fn import(resource: Arc<Self>) -> ImportedVirtualResourceState {
+        ImportedVirtualResourceState {
+            desc: resource.get_desc().clone().into(),
+            resource: ImportedVirtualResource::Buffer(resource),
+        }
+    }
+
+    fn borrow_resource(resource: &AnyFGResource) -> Option<&Self> {
+        match resource {
+            AnyFGResource::OwnedBuffer(resource) => Some(resource),
+            AnyFGResource::ImportedBuffer(resource) => Some(resource),
+            _ => None,
+        }
+    }
+
+    fn borrow_resource_mut(resource: &mut AnyFGResource) -> Option<&mut Self> {
+        match resource {
+            AnyFGResource::OwnedBuffer(resource) => Some(resource),
+            _ => None,
+        }
+    }
+}
+
+impl FGResourceDescriptor for BufferDescriptor {
+    type Resource = Buffer;
+}
+
+impl From<BufferDescriptor> for AnyFGResourceDescriptor {
+    fn from(value: BufferDescriptor) -> Self {
+        AnyFGResourceDescriptor::Buffer(value)
+    }
+}
@@ -1,6 +1,10 @@
+mod buffer;
 mod texture;
 
-use crate::{Device, Texture, TextureDescriptor, gfx_base::TypeHandle};
+use crate::{
+    Buffer, BufferDescriptor, BufferUsages, Device, ResourceUsage, Texture, TextureDescriptor,
+    gfx_base::TypeHandle,
+};
 use std::{fmt::Debug, hash::Hash, sync::Arc};
 
 use super::PassNode;
@@ -8,11 +12,69 @@ use super::PassNode;
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum AnyFGResourceDescriptor {
     Texture(TextureDescriptor),
+    Buffer(BufferDescriptor),
+}
+
+impl AnyFGResourceDescriptor {
+    pub fn size_in_bytes(&self) -> u64 {
+        match self {
+            AnyFGResourceDescriptor::Texture(desc) => desc.size_in_bytes(),
+            AnyFGResourceDescriptor::Buffer(desc) => desc.size,
+        }
+    }
+
+    ///除 usage 之外完全相同的描述可以共享同一块内存。可以映射的缓冲区由 CPU 读写，
+    ///内容不能被其他资源覆盖，也不能加入其他使用方式，不参与共享
+    pub fn is_alias_compatible(&self, other: &AnyFGResourceDescriptor) -> bool {
+        match (self, other) {
+            (AnyFGResourceDescriptor::Texture(desc), AnyFGResourceDescriptor::Texture(other)) => {
+                TextureDescriptor {
+                    usage: other.usage,
+                    ..desc.clone()
+                } == *other
+            }
+            (AnyFGResourceDescriptor::Buffer(desc), AnyFGResourceDescriptor::Buffer(other)) => {
+                let mappable = BufferUsages::MAP_READ | BufferUsages::MAP_WRITE;
+
+                desc.size == other.size
+                    && !desc.usage.intersects(mappable)
+                    && !other.usage.intersects(mappable)
+            }
+            _ => false,
+        }
+    }
+
+    pub fn add_usage(&mut self, usage: ResourceUsage) {
+        match self {
+            AnyFGResourceDescriptor::Texture(desc) => desc.usage |= usage.texture_usages(),
+            AnyFGResourceDescriptor::Buffer(desc) => desc.usage |= usage.buffer_usages(),
+        }
+    }
+
+    pub fn merge(&mut self, other: &AnyFGResourceDescriptor) {
+        match (self, other) {
+            (AnyFGResourceDescriptor::Texture(desc), AnyFGResourceDescriptor::Texture(other)) => {
+                desc.usage |= other.usage;
+            }
+            (AnyFGResourceDescriptor::Buffer(desc), AnyFGResourceDescriptor::Buffer(other)) => {
+                desc.usage |= other.usage;
+            }
+            _ => {}
+        }
+    }
 }
 
 impl Device {
-    pub fn create(&self, _desc: &AnyFGResourceDescriptor) -> AnyFGResource {
-        todo!()
+    ///label 为创建资源时的调试名称
+    pub fn create(&self, desc: &AnyFGResourceDescriptor, label: &str) -> AnyFGResource {
+        match desc {
+            AnyFGResourceDescriptor::Texture(desc) => {
+                AnyFGResource::OwnedTexture(self.create_texture_with_label(desc.clone(), label))
+            }
+            AnyFGResourceDescriptor::Buffer(desc) => {
+                AnyFGResource::OwnedBuffer(self.create_buffer_with_label(desc.clone(), label))
+            }
+        }
     }
 }
 
@@ -20,10 +82,19 @@ impl Device {
 pub enum AnyFGResource {
     OwnedTexture(Texture),
     ImportedTexture(Arc<Texture>),
+    OwnedBuffer(Buffer),
+    ImportedBuffer(Arc<Buffer>),
 }
 
 pub trait FGResource: 'static + Debug {
     type Descriptor: FGResourceDescriptor;
+
+    fn import(resource: Arc<Self>) -> ImportedVirtualResourceState;
+
+    fn borrow_resource(resource: &AnyFGResource) -> Option<&Self>;
+
+    ///导入的资源是共享的，不能被可变借用
+    fn borrow_resource_mut(resource: &mut AnyFGResource) -> Option<&mut Self>;
 }
 
 pub trait FGResourceDescriptor:
@@ -47,9 +118,32 @@ impl<T: Sized> TypeEquals for T {
 pub struct VirtualResource {
     pub info: ResourceInfo,
     pub state: VirtualResourceState,
+    ///跨帧保留的资源，不参与内存共享，也不会回收到 TransientResourceCache 中
+    pub persistent: bool,
+    ///只读资源不能被渲染节点写入
+    pub read_only: bool,
+    ///被异步队列上的渲染节点使用，compile 时设置。
+    ///生命周期延长到整帧，不参与内存共享
+    pub cross_queue: bool,
 }
 
 impl VirtualResource {
+    pub fn is_imported(&self) -> bool {
+        matches!(self.state, VirtualResourceState::Imported(_))
+    }
+
+    ///缓冲区返回 None
+    pub fn texture_desc(&self) -> Option<&TextureDescriptor> {
+        match &self.state {
+            VirtualResourceState::Setup(AnyFGResourceDescriptor::Texture(desc)) => Some(desc),
+            VirtualResourceState::Imported(state) => match &state.resource {
+                ImportedVirtualResource::Texture(texture) => Some(texture.get_desc()),
+                ImportedVirtualResource::Buffer(_) => None,
+            },
+            VirtualResourceState::Setup(AnyFGResourceDescriptor::Buffer(_)) => None,
+        }
+    }
+
     pub fn setup<ResourceType: FGResource>(
         name: &str,
         handle: TypeHandle<VirtualResource>,
@@ -58,19 +152,40 @@ impl VirtualResource {
         VirtualResource {
             state: VirtualResourceState::Setup(desc.into()),
             info: ResourceInfo::new(name, handle),
+            persistent: false,
+            read_only: false,
+            cross_queue: false,
+        }
+    }
+
+    pub fn imported<ResourceType: FGResource>(
+        name: &str,
+        handle: TypeHandle<VirtualResource>,
+        resource: Arc<ResourceType>,
+    ) -> VirtualResource {
+        VirtualResource {
+            state: VirtualResourceState::Imported(ResourceType::import(resource)),
+            info: ResourceInfo::new(name, handle),
+            persistent: false,
+            read_only: false,
+            cross_queue: false,
         }
     }
 }
 
+#[derive(Clone)]
 pub enum ImportedVirtualResource {
     Texture(Arc<Texture>),
+    Buffer(Arc<Buffer>),
 }
 
+#[derive(Clone)]
 pub struct ImportedVirtualResourceState {
     pub desc: AnyFGResourceDescriptor,
     pub resource: ImportedVirtualResource,
 }
 
+#[derive(Clone)]
 pub enum VirtualResourceState {
     Setup(AnyFGResourceDescriptor),
     Imported(ImportedVirtualResourceState),
@@ -1,9 +1,36 @@
+use std::sync::Arc;
+
 use crate::{Texture, TextureDescriptor};
 
-use super::{AnyFGResourceDescriptor, FGResource, FGResourceDescriptor};
+use super::{
+    AnyFGResource, AnyFGResourceDescriptor, FGResource, FGResourceDescriptor,
+    ImportedVirtualResource, ImportedVirtualResourceState,
+};
 
 impl FGResource for Texture {
     type Descriptor = TextureDescriptor;
+
+    fn import(resource: Arc<Self>) -> ImportedVirtualResourceState {
+        ImportedVirtualResourceState {
+            desc: resource.get_desc().clone().into(),
+            resource: ImportedVirtualResource::Texture(resource),
+        }
+    }
+
+    fn borrow_resource(resource: &AnyFGResource) -> Option<&Self> {
+        match resource {
+            AnyFGResource::OwnedTexture(resource) => Some(resource),
+            AnyFGResource::ImportedTexture(resource) => Some(resource),
+            _ => None,
+        }
+    }
+
+    fn borrow_resource_mut(resource: &mut AnyFGResource) -> Option<&mut Self> {
+        match resource {
+            AnyFGResource::OwnedTexture(resource) => Some(resource),
+            _ => None,
+        }
+    }
 }
 
 impl FGResourceDescriptor for TextureDescriptor {
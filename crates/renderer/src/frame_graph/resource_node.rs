@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use crate::gfx_base::TypeHandle;
+use crate::{TextureSubresourceRange, gfx_base::TypeHandle};
 
 use super::{PassNode, VirtualResource};
 
@@ -13,6 +13,12 @@ pub struct ResourceNode {
     pub version: u32,
     /// 当前写入此资源节点的渲染节点
     pub pass_node_writer_handle: Option<TypeHandle<PassNode>>,
+    /// 读取此资源节点的渲染节点数量
+    pub reader_count: u32,
+    /// 写入时所基于的资源节点
+    pub source_handle: Option<TypeHandle<ResourceNode>>,
+    /// 写入的纹理范围，只写入部分范围时其余范围保留 source_handle 的内容
+    pub range: TextureSubresourceRange,
 }
 
 impl ResourceNode {
@@ -26,6 +32,9 @@ impl ResourceNode {
             version,
             pass_node_writer_handle: None,
             resource_handle,
+            reader_count: 0,
+            source_handle: None,
+            range: TextureSubresourceRange::ALL,
         }
     }
 }
@@ -7,6 +7,14 @@ use super::{
     VirtualResource, VirtualResourceState,
 };
 
+///渲染节点执行前需要申请的资源
+pub struct ResourceRequest {
+    pub handle: TypeHandle<VirtualResource>,
+    ///资源名称，作为新创建资源的调试名称。复用 TransientResourceCache 中的资源时保留原来的名称
+    pub name: String,
+    pub state: VirtualResourceState,
+}
+
 #[derive(Default)]
 pub struct ResourceTable {
     resources: HashMap<TypeHandle<VirtualResource>, AnyFGResource>,
@@ -15,29 +23,74 @@ pub struct ResourceTable {
 impl ResourceTable {
     pub fn request_resources(
         &mut self,
-        resource: &VirtualResource,
+        request: &ResourceRequest,
         device: &Device,
         transient_resource_cache: &mut TransientResourceCache,
     ) {
-        let handle = resource.info.handle;
+        let handle = request.handle;
 
-        let resource = match &resource.state {
+        let resource = match &request.state {
             VirtualResourceState::Imported(state) => match &state.resource {
                 ImportedVirtualResource::Texture(resource) => {
                     AnyFGResource::ImportedTexture(resource.clone())
                 }
+                ImportedVirtualResource::Buffer(resource) => {
+                    AnyFGResource::ImportedBuffer(resource.clone())
+                }
             },
             VirtualResourceState::Setup(desc) => match desc {
                 AnyFGResourceDescriptor::Texture(texture_desc) => transient_resource_cache
                     .get_image(texture_desc)
                     .map(AnyFGResource::OwnedTexture)
-                    .unwrap_or_else(|| device.create(desc)),
+                    .unwrap_or_else(|| device.create(desc, &request.name)),
+                AnyFGResourceDescriptor::Buffer(buffer_desc) => transient_resource_cache
+                    .get_buffer(buffer_desc)
+                    .map(AnyFGResource::OwnedBuffer)
+                    .unwrap_or_else(|| device.create(desc, &request.name)),
             },
         };
 
         self.resources.insert(handle, resource);
     }
 
+    pub fn get_resource(&self, handle: &TypeHandle<VirtualResource>) -> Option<&AnyFGResource> {
+        self.resources.get(handle)
+    }
+
+    pub fn get_resource_mut(
+        &mut self,
+        handle: &TypeHandle<VirtualResource>,
+    ) -> Option<&mut AnyFGResource> {
+        self.resources.get_mut(handle)
+    }
+
+    pub fn insert_resource(
+        &mut self,
+        handle: TypeHandle<VirtualResource>,
+        resource: AnyFGResource,
+    ) {
+        self.resources.insert(handle, resource);
+    }
+
+    ///把另一个资源表中的资源移动到此资源表中
+    pub fn append(&mut self, other: ResourceTable) {
+        self.resources.extend(other.resources);
+    }
+
+    ///取出资源，不会回收到 TransientResourceCache 中
+    pub fn take_resource(&mut self, handle: &TypeHandle<VirtualResource>) -> Option<AnyFGResource> {
+        self.resources.remove(handle)
+    }
+
+    ///释放剩余的所有资源，导入的资源直接丢弃
+    pub fn release_all(&mut self, transient_resource_cache: &mut TransientResourceCache) {
+        let handles = self.resources.keys().copied().collect::<Vec<_>>();
+
+        for handle in handles {
+            self.release_resource(&handle, transient_resource_cache);
+        }
+    }
+
     pub fn release_resource(
         &mut self,
         handle: &TypeHandle<VirtualResource>,
@@ -49,6 +102,10 @@ impl ResourceTable {
                 AnyFGResource::OwnedTexture(texture) => {
                     transient_resource_cache.insert_image(texture.get_desc().clone(), texture);
                 }
+                AnyFGResource::ImportedBuffer(_) => {}
+                AnyFGResource::OwnedBuffer(buffer) => {
+                    transient_resource_cache.insert_buffer(buffer.get_desc().clone(), buffer);
+                }
             }
         }
     }
@@ -0,0 +1,340 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    BarrierResource, Buffer, BufferDescriptor, BufferTrait, BufferUsages, CommandBuffer,
+    CommandBufferTrait, DeviceTrait, Extent3d, LoadOp, Operations, QueueSubmission, QueueType,
+    RenderPass, RenderPassInfo, RenderPassTrait, ResourceBarrier, StoreOp, SyncPoint,
+    TexelCopyBufferLayout, Texture, TextureDescriptor, TextureTrait, TextureUsages,
+    error::RendererError,
+};
+
+use super::{FrameGraph, Pass, PassKind, PassNodeBuilder, RenderContext};
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Submitted {
+    ///通过 DeviceTrait::submit 按顺序提交的命令数量
+    Serial(usize),
+    Queue {
+        queue: QueueType,
+        waits: Vec<SyncPoint>,
+        command_buffers: usize,
+        signal: Option<SyncPoint>,
+    },
+}
+
+///记录创建的资源、录制的命令和提交的测试设备，clone 之后共享记录
+#[derive(Debug, Clone)]
+pub(crate) struct RecordingDevice {
+    queues: Vec<QueueType>,
+    timestamps: bool,
+    log: Arc<Mutex<Vec<String>>>,
+    submitted: Arc<Mutex<Vec<Submitted>>>,
+}
+
+impl Default for RecordingDevice {
+    fn default() -> Self {
+        RecordingDevice {
+            queues: vec![QueueType::Graphics],
+            timestamps: false,
+            log: Default::default(),
+            submitted: Default::default(),
+        }
+    }
+}
+
+impl RecordingDevice {
+    pub fn with_queues(queues: Vec<QueueType>) -> Self {
+        RecordingDevice {
+            queues,
+            ..Default::default()
+        }
+    }
+
+    ///支持时间戳查询的图形设备
+    pub fn with_timestamps() -> Self {
+        RecordingDevice {
+            timestamps: true,
+            ..Default::default()
+        }
+    }
+
+    ///取出目前为止创建资源和录制命令的记录
+    pub fn take_log(&self) -> Vec<String> {
+        std::mem::take(&mut *self.log.lock().unwrap())
+    }
+
+    pub fn take_submitted(&self) -> Vec<Submitted> {
+        std::mem::take(&mut *self.submitted.lock().unwrap())
+    }
+
+    fn push(&self, entry: String) {
+        self.log.lock().unwrap().push(entry);
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct EmptyResource;
+
+///RecordingDevice 创建的资源，记录命令时用调试名称区分资源
+#[derive(Debug)]
+struct LabeledResource(String);
+
+impl TextureTrait for LabeledResource {}
+
+impl BufferTrait for LabeledResource {}
+
+fn texture_label(texture: &Texture) -> &str {
+    texture
+        .downcast_ref::<LabeledResource>()
+        .map_or("unlabeled", |resource| resource.0.as_str())
+}
+
+fn buffer_label(buffer: &Buffer) -> &str {
+    buffer
+        .downcast_ref::<LabeledResource>()
+        .map_or("unlabeled", |resource| resource.0.as_str())
+}
+
+///加载和存储操作，为空时附件只读
+fn ops_label<V>(ops: Option<&Operations<V>>) -> String {
+    let Some(ops) = ops else {
+        return "read_only".to_string();
+    };
+
+    let load = match ops.load {
+        LoadOp::Clear(_) => "clear",
+        LoadOp::Load => "load",
+    };
+    let store = match ops.store {
+        StoreOp::Store => "store",
+        StoreOp::Discard => "discard",
+    };
+
+    format!("{load}/{store}")
+}
+
+impl TextureTrait for EmptyResource {}
+
+impl BufferTrait for EmptyResource {}
+
+impl RenderPassTrait for EmptyResource {
+    fn do_init(&mut self, _render_context: &RenderContext) {}
+}
+
+#[derive(Debug)]
+struct RecordingCommandBuffer {
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl RecordingCommandBuffer {
+    fn push(&self, entry: String) {
+        self.log.lock().unwrap().push(entry);
+    }
+}
+
+impl CommandBufferTrait for RecordingCommandBuffer {
+    fn begin_render_pass(&mut self, _render_pass: RenderPass) {
+        self.push("begin_render_pass".to_string());
+    }
+
+    fn end_render_pass(&mut self) {
+        self.push("end_render_pass".to_string());
+    }
+
+    fn begin_compute_pass(&mut self) {
+        self.push("begin_compute_pass".to_string());
+    }
+
+    fn end_compute_pass(&mut self) {
+        self.push("end_compute_pass".to_string());
+    }
+
+    fn copy_texture_to_texture(&mut self, _: &Texture, _: &Texture, _: Extent3d) {
+        self.push("copy_texture_to_texture".to_string());
+    }
+
+    fn copy_texture_to_buffer(
+        &mut self,
+        _: &Texture,
+        _: &Buffer,
+        layout: TexelCopyBufferLayout,
+        _: Extent3d,
+    ) {
+        self.push(format!("copy_texture_to_buffer {}", layout.bytes_per_row));
+    }
+
+    fn copy_buffer_to_buffer(&mut self, _: &Buffer, _: u64, _: &Buffer, _: u64, size: u64) {
+        self.push(format!("copy_buffer_to_buffer {size}"));
+    }
+
+    fn next_subpass(&mut self) {
+        self.push("next_subpass".to_string());
+    }
+
+    fn pipeline_barrier(&mut self, barriers: &[ResourceBarrier]) {
+        for barrier in barriers {
+            let resource = match &barrier.resource {
+                BarrierResource::Texture { texture, .. } => texture_label(texture),
+                BarrierResource::Buffer(buffer) => buffer_label(buffer),
+            };
+
+            self.push(format!(
+                "barrier {resource} {:?} -> {:?}",
+                barrier.before, barrier.after
+            ));
+        }
+    }
+
+    fn write_timestamp(&mut self, index: u32) {
+        self.push(format!("write_timestamp {index}"));
+    }
+
+    fn push_debug_group(&mut self, label: &str) {
+        self.push(format!("push_debug_group {label}"));
+    }
+
+    fn pop_debug_group(&mut self) {
+        self.push("pop_debug_group".to_string());
+    }
+
+    fn insert_debug_marker(&mut self, label: &str) {
+        self.push(format!("insert_debug_marker {label}"));
+    }
+}
+
+impl DeviceTrait for RecordingDevice {
+    fn create_command_buffer(&self) -> CommandBuffer {
+        CommandBuffer::new(RecordingCommandBuffer {
+            log: self.log.clone(),
+        })
+    }
+
+    ///记录为 render_pass_info 加上每个附件的名称和操作，例如
+    ///`color msaa clear/discard resolve color, depth depth clear/store stencil read_only`
+    fn create_render_pass(&self, desc: RenderPassInfo) -> RenderPass {
+        let color_attachments = desc.color_attachments.iter().map(|attachment| {
+            let mut entry = format!(
+                "color {} {}",
+                texture_label(attachment.view),
+                ops_label(Some(&attachment.ops))
+            );
+            if let Some(resolve_target) = attachment.resolve_target {
+                entry.push_str(&format!(" resolve {}", texture_label(resolve_target)));
+            }
+
+            entry
+        });
+        let depth_stencil_attachment = desc.depth_stencil_attachment.iter().map(|attachment| {
+            format!(
+                "depth {} depth {} stencil {}",
+                texture_label(attachment.view),
+                ops_label(attachment.depth_ops.as_ref()),
+                ops_label(attachment.stencil_ops.as_ref())
+            )
+        });
+        let attachments = color_attachments
+            .chain(depth_stencil_attachment)
+            .collect::<Vec<_>>();
+
+        self.push(format!("render_pass_info {}", attachments.join(", ")));
+
+        RenderPass::new(EmptyResource)
+    }
+
+    fn create_texture(&self, desc: TextureDescriptor) -> Texture {
+        self.push("create_texture".to_string());
+        Texture::new(EmptyResource, desc)
+    }
+
+    fn create_buffer(&self, desc: BufferDescriptor) -> Buffer {
+        self.push("create_buffer".to_string());
+        Buffer::new(EmptyResource, desc)
+    }
+
+    fn create_texture_with_label(&self, desc: TextureDescriptor, label: &str) -> Texture {
+        self.push(format!("create_texture {label}"));
+        Texture::new(LabeledResource(label.to_string()), desc)
+    }
+
+    fn create_buffer_with_label(&self, desc: BufferDescriptor, label: &str) -> Buffer {
+        self.push(format!("create_buffer {label}"));
+        Buffer::new(LabeledResource(label.to_string()), desc)
+    }
+
+    fn submit(&self, command_buffers: Vec<CommandBuffer>) {
+        self.submitted
+            .lock()
+            .unwrap()
+            .push(Submitted::Serial(command_buffers.len()));
+    }
+
+    fn read_buffer(&self, buffer: &Buffer) -> Vec<u8> {
+        vec![0; buffer.get_desc().size as usize]
+    }
+
+    fn queues(&self) -> Vec<QueueType> {
+        self.queues.clone()
+    }
+
+    fn submit_queue(&self, submission: QueueSubmission) {
+        self.submitted.lock().unwrap().push(Submitted::Queue {
+            queue: submission.queue,
+            waits: submission.waits,
+            command_buffers: submission.command_buffers.len(),
+            signal: submission.signal,
+        });
+    }
+
+    fn supports_timestamps(&self) -> bool {
+        self.timestamps
+    }
+
+    ///每个时间戳比前一个晚 1 微秒
+    fn read_timestamps(&self, count: u32) -> Vec<u64> {
+        (0..count as u64).map(|index| index * 1000).collect()
+    }
+}
+
+pub(crate) struct EmptyPass;
+
+impl Pass for EmptyPass {
+    fn setup(&mut self, _builder: &mut PassNodeBuilder) {}
+
+    fn execute(&mut self, _render_context: &mut RenderContext) -> Result<(), RendererError> {
+        Ok(())
+    }
+}
+
+pub(crate) fn buffer_desc() -> BufferDescriptor {
+    BufferDescriptor {
+        size: 256,
+        usage: BufferUsages::STORAGE,
+    }
+}
+
+pub(crate) fn texture_desc(width: u32) -> TextureDescriptor {
+    TextureDescriptor {
+        size: Extent3d {
+            width,
+            height: width,
+            depth_or_array_layers: 1,
+        },
+        usage: TextureUsages::RENDER_ATTACHMENT,
+        ..Default::default()
+    }
+}
+
+///添加一个不录制命令的渲染节点，返回 setup 的返回值
+pub(crate) fn add_pass<Output>(
+    fg: &mut FrameGraph,
+    insert_point: usize,
+    name: &str,
+    kind: PassKind,
+    setup: impl FnOnce(&mut PassNodeBuilder) -> Output,
+) -> Output {
+    fg.add_pass_with_output(insert_point, name, kind, |builder| {
+        let output = setup(builder);
+
+        (Box::new(EmptyPass), output)
+    })
+}
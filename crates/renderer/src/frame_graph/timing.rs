@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+///一个渲染节点在一帧中的执行时间
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassTiming {
+    pub name: String,
+    ///录制命令花费的 CPU 时间
+    pub cpu_time: Duration,
+    ///GPU 执行的时间，设备不支持时间戳查询时为空
+    pub gpu_time: Option<Duration>,
+    ///开始和结束的时间戳查询分别为 timestamp_index 和 timestamp_index + 1
+    timestamp_index: Option<u32>,
+}
+
+impl PassTiming {
+    pub(crate) fn new(name: &str, cpu_time: Duration, timestamp_index: Option<u32>) -> Self {
+        PassTiming {
+            name: name.to_string(),
+            cpu_time,
+            gpu_time: None,
+            timestamp_index,
+        }
+    }
+}
+
+///最近一次 execute 中每个渲染节点的执行时间，按执行顺序排列
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrameTimingReport {
+    pub passes: Vec<PassTiming>,
+}
+
+impl FrameTimingReport {
+    ///名称重复时返回第一个执行的渲染节点
+    pub fn get(&self, name: &str) -> Option<&PassTiming> {
+        self.passes.iter().find(|timing| timing.name == name)
+    }
+
+    pub fn cpu_time(&self) -> Duration {
+        self.passes.iter().map(|timing| timing.cpu_time).sum()
+    }
+
+    ///有渲染节点没有 GPU 时间时为空
+    pub fn gpu_time(&self) -> Option<Duration> {
+        self.passes.iter().map(|timing| timing.gpu_time).sum()
+    }
+
+    ///需要读取的时间戳查询数量
+    pub(crate) fn timestamp_count(&self) -> u32 {
+        self.passes
+            .iter()
+            .filter_map(|timing| timing.timestamp_index)
+            .map(|index| index + 2)
+            .max()
+            .unwrap_or_default()
+    }
+
+    ///timestamps 为读取的时间戳，单位为纳秒
+    pub(crate) fn resolve_gpu_times(&mut self, timestamps: &[u64]) {
+        for timing in self.passes.iter_mut() {
+            let Some(index) = timing.timestamp_index else {
+                continue;
+            };
+
+            if let (Some(begin), Some(end)) = (
+                timestamps.get(index as usize),
+                timestamps.get(index as usize + 1),
+            ) {
+                timing.gpu_time = Some(Duration::from_nanos(end.saturating_sub(*begin)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{Device, FrameGraph, PassKind, TransientResourceCache};
+
+    use super::super::testing::{RecordingDevice, add_pass, texture_desc};
+
+    ///gbuffer -> lighting
+    fn execute(recording: &RecordingDevice) -> FrameGraph {
+        let mut fg = FrameGraph::default();
+        let color = fg.create("color", texture_desc(64));
+
+        let color = add_pass(&mut fg, 0, "gbuffer", PassKind::Render, |builder| {
+            builder.write(color).handle()
+        });
+        add_pass(&mut fg, 1, "lighting", PassKind::Render, |builder| {
+            builder.read(color);
+            builder.side_effect();
+        });
+
+        fg.compile().unwrap();
+        fg.execute(
+            &Device::new(recording.clone()),
+            &mut TransientResourceCache::default(),
+        )
+        .unwrap();
+
+        fg
+    }
+
+    fn timestamps(recording: &RecordingDevice) -> Vec<String> {
+        recording
+            .take_log()
+            .into_iter()
+            .filter(|entry| entry.starts_with("write_timestamp"))
+            .collect()
+    }
+
+    #[test]
+    fn gpu_times_come_from_timestamp_queries() {
+        let recording = RecordingDevice::with_timestamps();
+        let fg = execute(&recording);
+        let report = fg.timing_report();
+
+        let names = report
+            .passes
+            .iter()
+            .map(|timing| timing.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["gbuffer", "lighting"]);
+
+        assert_eq!(
+            timestamps(&recording),
+            [
+                "write_timestamp 0",
+                "write_timestamp 1",
+                "write_timestamp 2",
+                "write_timestamp 3"
+            ]
+        );
+
+        //测试设备的每个时间戳比前一个晚 1 微秒
+        let micros = Duration::from_micros(1);
+        assert_eq!(report.get("lighting").unwrap().gpu_time, Some(micros));
+        assert_eq!(report.gpu_time(), Some(2 * micros));
+    }
+
+    #[test]
+    fn devices_without_timestamps_report_cpu_times_only() {
+        let recording = RecordingDevice::default();
+        let fg = execute(&recording);
+        let report = fg.timing_report();
+
+        assert!(timestamps(&recording).is_empty());
+        assert_eq!(report.passes.len(), 2);
+        assert!(report.passes.iter().all(|timing| timing.gpu_time.is_none()));
+        assert_eq!(report.gpu_time(), None);
+    }
+}
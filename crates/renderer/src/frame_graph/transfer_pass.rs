@@ -0,0 +1,253 @@
+use crate::{
+    Buffer, Device, Extent3d, TexelCopyBufferLayout, Texture, TextureFormat, error::RendererError,
+};
+
+use super::{
+    FrameGraph, GpuRead, GpuWrite, Pass, PassKind, PassNodeBuilder, RenderContext,
+    ResourceNodeHandle, ResourceRef,
+};
+
+///从 GPU 读取的纹理数据
+#[derive(Debug, Clone)]
+pub struct TextureReadback {
+    pub size: Extent3d,
+    pub format: TextureFormat,
+    pub layout: TexelCopyBufferLayout,
+    pub data: Vec<u8>,
+}
+
+pub type ReadbackFn = Box<dyn FnOnce(TextureReadback) + Send>;
+
+///已经录制复制命令，等待提交后读取的缓冲区
+pub(crate) struct PendingReadback {
+    pub buffer: Buffer,
+    pub size: Extent3d,
+    pub format: TextureFormat,
+    pub layout: TexelCopyBufferLayout,
+    pub callback: ReadbackFn,
+}
+
+impl TextureReadback {
+    ///layout 为复制时每行带有填充的排列方式，去掉填充后数据为紧密排列
+    pub(crate) fn from_padded(
+        size: Extent3d,
+        format: TextureFormat,
+        padded_layout: TexelCopyBufferLayout,
+        data: Vec<u8>,
+    ) -> Self {
+        let layout = TexelCopyBufferLayout {
+            bytes_per_row: size.width * format.block_size(),
+            ..padded_layout
+        };
+
+        let data = if layout == padded_layout {
+            data
+        } else {
+            let row_size = layout.bytes_per_row as usize;
+
+            data.chunks(padded_layout.bytes_per_row as usize)
+                .flat_map(|row| &row[..row.len().min(row_size)])
+                .copied()
+                .collect()
+        };
+
+        TextureReadback {
+            size,
+            format,
+            layout,
+            data,
+        }
+    }
+}
+
+impl PendingReadback {
+    ///命令执行完成后把紧密排列的数据交给 callback
+    pub(crate) fn read(self, device: &Device) {
+        let PendingReadback {
+            buffer,
+            size,
+            format,
+            layout,
+            callback,
+        } = self;
+
+        device.read_buffer_async(
+            buffer,
+            Box::new(move |data| {
+                callback(TextureReadback::from_padded(size, format, layout, data))
+            }),
+        );
+    }
+}
+
+pub struct CopyTexturePass {
+    source: ResourceRef<Texture, GpuRead>,
+    destination: ResourceRef<Texture, GpuWrite>,
+}
+
+impl Pass for CopyTexturePass {
+    fn setup(&mut self, _builder: &mut PassNodeBuilder) {}
+
+    fn execute(&mut self, render_context: &mut RenderContext) -> Result<(), RendererError> {
+        render_context.copy_texture_to_texture(&self.source, &self.destination)
+    }
+}
+
+pub struct CopyTextureToBufferPass {
+    source: ResourceRef<Texture, GpuRead>,
+    destination: ResourceRef<Buffer, GpuWrite>,
+}
+
+impl Pass for CopyTextureToBufferPass {
+    fn setup(&mut self, _builder: &mut PassNodeBuilder) {}
+
+    fn execute(&mut self, render_context: &mut RenderContext) -> Result<(), RendererError> {
+        render_context.copy_texture_to_buffer(&self.source, &self.destination)
+    }
+}
+
+pub struct CopyBufferPass {
+    source: ResourceRef<Buffer, GpuRead>,
+    destination: ResourceRef<Buffer, GpuWrite>,
+}
+
+impl Pass for CopyBufferPass {
+    fn setup(&mut self, _builder: &mut PassNodeBuilder) {}
+
+    fn execute(&mut self, render_context: &mut RenderContext) -> Result<(), RendererError> {
+        render_context.copy_buffer_to_buffer(&self.source, &self.destination)
+    }
+}
+
+pub struct ReadbackPass {
+    source: ResourceRef<Texture, GpuRead>,
+    callback: Option<ReadbackFn>,
+}
+
+impl Pass for ReadbackPass {
+    fn setup(&mut self, _builder: &mut PassNodeBuilder) {}
+
+    fn execute(&mut self, render_context: &mut RenderContext) -> Result<(), RendererError> {
+        match self.callback.take() {
+            Some(callback) => render_context.readback_texture(&self.source, callback),
+            None => Ok(()),
+        }
+    }
+}
+
+impl FrameGraph {
+    ///返回写入后的目标纹理
+    pub fn add_copy_texture_pass(
+        &mut self,
+        insert_point: usize,
+        name: &str,
+        source: ResourceNodeHandle<Texture>,
+        destination: ResourceNodeHandle<Texture>,
+    ) -> ResourceNodeHandle<Texture> {
+        self.add_pass_with_output(insert_point, name, PassKind::Transfer, |builder| {
+            let pass = CopyTexturePass {
+                source: builder.read(source),
+                destination: builder.write(destination),
+            };
+            let output = pass.destination.handle();
+
+            (Box::new(pass), output)
+        })
+    }
+
+    ///返回写入后的目标缓冲区
+    pub fn add_copy_texture_to_buffer_pass(
+        &mut self,
+        insert_point: usize,
+        name: &str,
+        source: ResourceNodeHandle<Texture>,
+        destination: ResourceNodeHandle<Buffer>,
+    ) -> ResourceNodeHandle<Buffer> {
+        self.add_pass_with_output(insert_point, name, PassKind::Transfer, |builder| {
+            let pass = CopyTextureToBufferPass {
+                source: builder.read(source),
+                destination: builder.write(destination),
+            };
+            let output = pass.destination.handle();
+
+            (Box::new(pass), output)
+        })
+    }
+
+    ///返回写入后的目标缓冲区
+    pub fn add_copy_buffer_pass(
+        &mut self,
+        insert_point: usize,
+        name: &str,
+        source: ResourceNodeHandle<Buffer>,
+        destination: ResourceNodeHandle<Buffer>,
+    ) -> ResourceNodeHandle<Buffer> {
+        self.add_pass_with_output(insert_point, name, PassKind::Transfer, |builder| {
+            let pass = CopyBufferPass {
+                source: builder.read(source),
+                destination: builder.write(destination),
+            };
+            let output = pass.destination.handle();
+
+            (Box::new(pass), output)
+        })
+    }
+
+    ///在本帧提交并执行完成后把纹理数据交给 callback，回读节点不会被剔除
+    pub fn add_readback_pass(
+        &mut self,
+        insert_point: usize,
+        name: &str,
+        source: ResourceNodeHandle<Texture>,
+        callback: impl FnOnce(TextureReadback) + Send + 'static,
+    ) {
+        self.add_pass_with_output(insert_point, name, PassKind::Transfer, |builder| {
+            builder.side_effect();
+
+            let pass = ReadbackPass {
+                source: builder.read(source),
+                callback: Some(Box::new(callback)),
+            };
+
+            (Box::new(pass), ())
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Extent3d, TexelCopyBufferLayout, TextureDescriptor, TextureFormat};
+
+    use super::TextureReadback;
+
+    #[test]
+    fn readback_rows_are_unpadded() {
+        let desc = TextureDescriptor {
+            size: Extent3d {
+                width: 3,
+                height: 2,
+                depth_or_array_layers: 1,
+            },
+            format: TextureFormat::Rgba8Unorm,
+            ..Default::default()
+        };
+        let layout = TexelCopyBufferLayout::aligned(&desc);
+        assert_eq!(layout.bytes_per_row, 256);
+
+        //每行 12 字节的像素数据之后是填充
+        let data = (0..2u8)
+            .flat_map(|row| {
+                let mut bytes = vec![row + 1; 12];
+                bytes.resize(256, 0xff);
+                bytes
+            })
+            .collect();
+
+        let readback = TextureReadback::from_padded(desc.size, desc.format, layout, data);
+        assert_eq!(
+            readback.layout,
+            TexelCopyBufferLayout::tightly_packed(&desc)
+        );
+        assert_eq!(readback.data, [vec![1; 12], vec![2; 12]].concat());
+    }
+}
@@ -1,26 +1,330 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, hash::Hash};
 
-use crate::{Texture, TextureDescriptor};
+use crate::{Buffer, BufferDescriptor, Texture, TextureDescriptor};
+
+///TransientResourceCache 的回收策略，默认不限制，缓存的资源只在 clear 时释放
+#[derive(Debug, Clone, Default)]
+pub struct TransientResourceCacheOptions {
+    ///连续多少帧没有被使用的资源会被释放，为 None 时不按帧数释放
+    pub max_unused_frames: Option<u64>,
+    ///缓存资源占用的显存上限，超出时优先释放最久没有使用的资源
+    pub byte_budget: Option<u64>,
+}
+
+///缓存的使用统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransientResourceCacheStats {
+    ///从缓存中取得资源的次数
+    pub hits: u64,
+    ///缓存中没有可用资源，需要重新创建的次数
+    pub misses: u64,
+    ///缓存资源占用的显存
+    pub bytes_held: u64,
+    pub resource_count: usize,
+    ///上一帧新创建的资源数量
+    pub allocations_last_frame: u64,
+    ///累计被释放的缓存资源数量
+    pub evictions: u64,
+}
+
+impl TransientResourceCacheStats {
+    pub fn hit_rate(&self) -> f32 {
+        let total = self.hits + self.misses;
+
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f32 / total as f32
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CacheEntry<ResourceType> {
+    resource: ResourceType,
+    ///最后一次被释放回缓存的帧
+    last_used_frame: u64,
+}
 
 #[derive(Default, Debug)]
 pub struct TransientResourceCache {
-    textures: HashMap<TextureDescriptor, Vec<Texture>>,
+    textures: HashMap<TextureDescriptor, Vec<CacheEntry<Texture>>>,
+    buffers: HashMap<BufferDescriptor, Vec<CacheEntry<Buffer>>>,
+    options: TransientResourceCacheOptions,
+    frame: u64,
+    allocations: u64,
+    stats: TransientResourceCacheStats,
 }
 
 impl TransientResourceCache {
-    pub fn get_image(&mut self, desc: &TextureDescriptor) -> Option<Texture> {
-        if let Some(entry) = self.textures.get_mut(desc) {
-            entry.pop()
-        } else {
-            None
+    pub fn new(options: TransientResourceCacheOptions) -> Self {
+        TransientResourceCache {
+            options,
+            ..Default::default()
         }
     }
 
+    pub fn set_options(&mut self, options: TransientResourceCacheOptions) {
+        self.options = options;
+    }
+
+    pub fn options(&self) -> &TransientResourceCacheOptions {
+        &self.options
+    }
+
+    pub fn stats(&self) -> TransientResourceCacheStats {
+        self.stats
+    }
+
+    pub fn get_image(&mut self, desc: &TextureDescriptor) -> Option<Texture> {
+        let texture = Self::take(&mut self.textures, desc);
+        self.record_get(texture.is_some(), desc.size_in_bytes());
+
+        texture
+    }
+
     pub fn insert_image(&mut self, desc: TextureDescriptor, resource: Texture) {
-        if let Some(entry) = self.textures.get_mut(&desc) {
-            entry.push(resource);
+        self.record_insert(desc.size_in_bytes());
+        Self::insert(&mut self.textures, desc, resource, self.frame);
+    }
+
+    pub fn get_buffer(&mut self, desc: &BufferDescriptor) -> Option<Buffer> {
+        let buffer = Self::take(&mut self.buffers, desc);
+        self.record_get(buffer.is_some(), desc.size);
+
+        buffer
+    }
+
+    pub fn insert_buffer(&mut self, desc: BufferDescriptor, resource: Buffer) {
+        self.record_insert(desc.size);
+        Self::insert(&mut self.buffers, desc, resource, self.frame);
+    }
+
+    ///释放所有缓存的资源，保留统计
+    pub fn clear(&mut self) {
+        let count = self.stats.resource_count as u64;
+
+        self.textures.clear();
+        self.buffers.clear();
+
+        self.stats.evictions += count;
+        self.stats.bytes_held = 0;
+        self.stats.resource_count = 0;
+    }
+
+    ///一帧执行结束后调用，释放长时间没有使用的资源和超出预算的资源
+    pub fn end_frame(&mut self) {
+        if let Some(max_unused_frames) = self.options.max_unused_frames {
+            let frame = self.frame;
+            self.evict_where(|last_used_frame| frame - last_used_frame >= max_unused_frames);
+        }
+
+        if let Some(byte_budget) = self.options.byte_budget {
+            self.evict_to_budget(byte_budget);
+        }
+
+        self.stats.allocations_last_frame = std::mem::take(&mut self.allocations);
+        self.frame += 1;
+    }
+
+    fn evict_where(&mut self, should_evict: impl Fn(u64) -> bool) {
+        let mut evicted = (0, 0);
+
+        Self::retain(&mut self.textures, &mut evicted, |entry| {
+            (
+                !should_evict(entry.last_used_frame),
+                entry.resource.get_desc().size_in_bytes(),
+            )
+        });
+        Self::retain(&mut self.buffers, &mut evicted, |entry| {
+            (
+                !should_evict(entry.last_used_frame),
+                entry.resource.get_desc().size,
+            )
+        });
+
+        self.record_evictions(evicted);
+    }
+
+    ///按最后使用的帧从旧到新释放，直到缓存的显存不超过预算
+    fn evict_to_budget(&mut self, byte_budget: u64) {
+        if self.stats.bytes_held <= byte_budget {
+            return;
+        }
+
+        let mut last_used_frames = self
+            .textures
+            .values()
+            .flatten()
+            .map(|entry| {
+                (
+                    entry.last_used_frame,
+                    entry.resource.get_desc().size_in_bytes(),
+                )
+            })
+            .chain(
+                self.buffers
+                    .values()
+                    .flatten()
+                    .map(|entry| (entry.last_used_frame, entry.resource.get_desc().size)),
+            )
+            .collect::<Vec<_>>();
+        last_used_frames.sort_by_key(|(last_used_frame, _)| *last_used_frame);
+
+        //找到需要释放的最新一帧，同一帧释放的资源一起释放
+        let mut bytes_held = self.stats.bytes_held;
+        let mut evict_before = 0;
+        for (last_used_frame, size) in last_used_frames {
+            if bytes_held <= byte_budget {
+                break;
+            }
+
+            bytes_held -= size;
+            evict_before = last_used_frame + 1;
+        }
+
+        self.evict_where(|last_used_frame| last_used_frame < evict_before);
+    }
+
+    fn take<Descriptor: Hash + Eq, ResourceType>(
+        entries: &mut HashMap<Descriptor, Vec<CacheEntry<ResourceType>>>,
+        desc: &Descriptor,
+    ) -> Option<ResourceType> {
+        entries
+            .get_mut(desc)
+            .and_then(|entry| entry.pop())
+            .map(|entry| entry.resource)
+    }
+
+    fn insert<Descriptor: Hash + Eq, ResourceType>(
+        entries: &mut HashMap<Descriptor, Vec<CacheEntry<ResourceType>>>,
+        desc: Descriptor,
+        resource: ResourceType,
+        frame: u64,
+    ) {
+        entries.entry(desc).or_default().push(CacheEntry {
+            resource,
+            last_used_frame: frame,
+        });
+    }
+
+    ///keep 返回是否保留以及资源大小，evicted 累计释放的数量和大小
+    fn retain<Descriptor, ResourceType>(
+        entries: &mut HashMap<Descriptor, Vec<CacheEntry<ResourceType>>>,
+        evicted: &mut (u64, u64),
+        keep: impl Fn(&CacheEntry<ResourceType>) -> (bool, u64),
+    ) {
+        entries.retain(|_, entry| {
+            entry.retain(|entry| {
+                let (keep, size) = keep(entry);
+
+                if !keep {
+                    evicted.0 += 1;
+                    evicted.1 += size;
+                }
+
+                keep
+            });
+
+            !entry.is_empty()
+        });
+    }
+
+    fn record_get(&mut self, hit: bool, size: u64) {
+        if hit {
+            self.stats.hits += 1;
+            self.stats.bytes_held -= size;
+            self.stats.resource_count -= 1;
         } else {
-            self.textures.insert(desc, vec![resource]);
+            self.stats.misses += 1;
+            self.allocations += 1;
+        }
+    }
+
+    fn record_insert(&mut self, size: u64) {
+        self.stats.bytes_held += size;
+        self.stats.resource_count += 1;
+    }
+
+    fn record_evictions(&mut self, (count, size): (u64, u64)) {
+        self.stats.evictions += count;
+        self.stats.bytes_held -= size;
+        self.stats.resource_count -= count as usize;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Buffer, BufferDescriptor};
+
+    use super::{
+        super::testing::{EmptyResource, buffer_desc},
+        TransientResourceCache, TransientResourceCacheOptions,
+    };
+
+    fn insert_buffer(cache: &mut TransientResourceCache, desc: &BufferDescriptor) {
+        cache.insert_buffer(desc.clone(), Buffer::new(EmptyResource, desc.clone()));
+    }
+
+    #[test]
+    fn default_options_keep_unused_resources() {
+        let mut cache = TransientResourceCache::default();
+        let desc = buffer_desc();
+
+        insert_buffer(&mut cache, &desc);
+        for _ in 0..16 {
+            cache.end_frame();
         }
+
+        assert_eq!(cache.stats().resource_count, 1);
+        assert_eq!(cache.stats().evictions, 0);
+        assert!(cache.get_buffer(&desc).is_some());
+    }
+
+    #[test]
+    fn unused_resources_are_evicted_after_max_unused_frames() {
+        let mut cache = TransientResourceCache::new(TransientResourceCacheOptions {
+            max_unused_frames: Some(2),
+            byte_budget: None,
+        });
+        let desc = buffer_desc();
+
+        insert_buffer(&mut cache, &desc);
+        cache.end_frame();
+        cache.end_frame();
+        assert_eq!(cache.stats().resource_count, 1);
+
+        cache.end_frame();
+        assert_eq!(cache.stats().resource_count, 0);
+        assert_eq!(cache.stats().bytes_held, 0);
+        assert_eq!(cache.stats().evictions, 1);
+        assert!(cache.get_buffer(&desc).is_none());
+    }
+
+    #[test]
+    fn oldest_resources_are_evicted_over_budget() {
+        let mut cache = TransientResourceCache::new(TransientResourceCacheOptions {
+            max_unused_frames: None,
+            byte_budget: Some(512),
+        });
+        let old = buffer_desc();
+        let new = BufferDescriptor {
+            size: 512,
+            ..buffer_desc()
+        };
+
+        insert_buffer(&mut cache, &old);
+        cache.end_frame();
+        insert_buffer(&mut cache, &new);
+        cache.end_frame();
+
+        assert_eq!(cache.stats().bytes_held, 512);
+        assert_eq!(cache.stats().evictions, 1);
+        assert!(cache.get_buffer(&old).is_none());
+        assert!(cache.get_buffer(&new).is_some());
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+        assert_eq!(stats.bytes_held, 0);
     }
 }
@@ -0,0 +1,83 @@
+use super::{Buffer, BufferUsages, Texture, TextureSubresourceRange, TextureUsages};
+
+///资源在渲染节点中的使用方式，决定资源的用途和屏障前后的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ResourceUsage {
+    ///资源还没有被使用过，内容未定义
+    #[default]
+    Undefined,
+    Sampled,
+    Uniform,
+    Vertex,
+    Index,
+    Indirect,
+    StorageRead,
+    StorageWrite,
+    ColorAttachment,
+    DepthStencilRead,
+    DepthStencilWrite,
+    CopySrc,
+    CopyDst,
+}
+
+impl ResourceUsage {
+    pub fn is_write(&self) -> bool {
+        matches!(
+            self,
+            ResourceUsage::StorageWrite
+                | ResourceUsage::ColorAttachment
+                | ResourceUsage::DepthStencilWrite
+                | ResourceUsage::CopyDst
+        )
+    }
+
+    pub fn texture_usages(&self) -> TextureUsages {
+        match self {
+            ResourceUsage::Sampled => TextureUsages::TEXTURE_BINDING,
+            ResourceUsage::StorageRead | ResourceUsage::StorageWrite => {
+                TextureUsages::STORAGE_BINDING
+            }
+            ResourceUsage::ColorAttachment
+            | ResourceUsage::DepthStencilRead
+            | ResourceUsage::DepthStencilWrite => TextureUsages::RENDER_ATTACHMENT,
+            ResourceUsage::CopySrc => TextureUsages::COPY_SRC,
+            ResourceUsage::CopyDst => TextureUsages::COPY_DST,
+            _ => TextureUsages::empty(),
+        }
+    }
+
+    pub fn buffer_usages(&self) -> BufferUsages {
+        match self {
+            ResourceUsage::Sampled | ResourceUsage::Uniform => BufferUsages::UNIFORM,
+            ResourceUsage::Vertex => BufferUsages::VERTEX,
+            ResourceUsage::Index => BufferUsages::INDEX,
+            ResourceUsage::Indirect => BufferUsages::INDIRECT,
+            ResourceUsage::StorageRead | ResourceUsage::StorageWrite => BufferUsages::STORAGE,
+            ResourceUsage::CopySrc => BufferUsages::COPY_SRC,
+            ResourceUsage::CopyDst => BufferUsages::COPY_DST,
+            _ => BufferUsages::empty(),
+        }
+    }
+
+    ///使用方式改变，或者连续写入时需要屏障
+    pub fn needs_barrier(before: ResourceUsage, after: ResourceUsage) -> bool {
+        before != after || after.is_write()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum BarrierResource<'a> {
+    Texture {
+        texture: &'a Texture,
+        range: TextureSubresourceRange,
+    },
+    Buffer(&'a Buffer),
+}
+
+///资源从 before 状态转换到 after 状态
+#[derive(Debug, Clone)]
+pub struct ResourceBarrier<'a> {
+    pub resource: BarrierResource<'a>,
+    pub before: ResourceUsage,
+    pub after: ResourceUsage,
+}
@@ -0,0 +1,41 @@
+use crate::{define_atomic_id, define_gfx_frame_graph_type};
+use bitflags::bitflags;
+use downcast_rs::Downcast;
+use std::fmt::Debug;
+
+define_atomic_id!(BufferId);
+
+pub trait BufferTrait: 'static + Sync + Send + Debug {}
+
+pub trait ErasedBufferTrait: 'static + Sync + Send + Debug + Downcast {}
+
+impl<T: BufferTrait> ErasedBufferTrait for T {}
+
+define_gfx_frame_graph_type!(
+    Buffer,
+    BufferId,
+    BufferTrait,
+    ErasedBufferTrait,
+    BufferDescriptor
+);
+
+bitflags! {
+    #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+    pub struct BufferUsages: u32 {
+        const MAP_READ = 1 << 0;
+        const MAP_WRITE = 1 << 1;
+        const COPY_SRC = 1 << 2;
+        const COPY_DST = 1 << 3;
+        const INDEX = 1 << 4;
+        const VERTEX = 1 << 5;
+        const UNIFORM = 1 << 6;
+        const STORAGE = 1 << 7;
+        const INDIRECT = 1 << 8;
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct BufferDescriptor {
+    pub size: u64,
+    pub usage: BufferUsages,
+}
@@ -4,20 +4,133 @@ use downcast_rs::Downcast;
 
 use crate::{define_atomic_id, define_gfx_type};
 
-use super::RenderPass;
+use super::{Buffer, Extent3d, RenderPass, ResourceBarrier, Texture, TextureDescriptor};
 
 define_atomic_id!(CommandBufferId);
 
+///纹理复制到缓冲区时 bytes_per_row 必须是此值的倍数
+pub const COPY_BYTES_PER_ROW_ALIGNMENT: u32 = 256;
+
+///纹理数据在缓冲区中的排列方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TexelCopyBufferLayout {
+    pub offset: u64,
+    pub bytes_per_row: u32,
+    pub rows_per_image: u32,
+}
+
+impl TexelCopyBufferLayout {
+    ///行与行之间没有填充的排列方式
+    pub fn tightly_packed(desc: &TextureDescriptor) -> Self {
+        TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: desc.size.width * desc.format.block_size(),
+            rows_per_image: desc.size.height,
+        }
+    }
+
+    ///每行填充到 COPY_BYTES_PER_ROW_ALIGNMENT 的倍数，纹理复制到缓冲区时使用
+    pub fn aligned(desc: &TextureDescriptor) -> Self {
+        let layout = TexelCopyBufferLayout::tightly_packed(desc);
+
+        TexelCopyBufferLayout {
+            bytes_per_row: layout
+                .bytes_per_row
+                .next_multiple_of(COPY_BYTES_PER_ROW_ALIGNMENT),
+            ..layout
+        }
+    }
+
+    pub fn size_in_bytes(&self, size: Extent3d) -> u64 {
+        self.bytes_per_row as u64 * self.rows_per_image as u64 * size.depth_or_array_layers as u64
+    }
+}
+
 pub trait CommandBufferTrait: 'static + Sync + Send + Debug {
     fn begin_render_pass(&mut self, render_pass: RenderPass);
 
     fn end_render_pass(&mut self);
+
+    fn begin_compute_pass(&mut self);
+
+    fn end_compute_pass(&mut self);
+
+    fn copy_texture_to_texture(&mut self, source: &Texture, destination: &Texture, size: Extent3d);
+
+    fn copy_texture_to_buffer(
+        &mut self,
+        source: &Texture,
+        destination: &Buffer,
+        layout: TexelCopyBufferLayout,
+        size: Extent3d,
+    );
+
+    fn copy_buffer_to_buffer(
+        &mut self,
+        source: &Buffer,
+        source_offset: u64,
+        destination: &Buffer,
+        destination_offset: u64,
+        size: u64,
+    );
+
+    ///切换到渲染通道的下一个子通道，不支持子通道的后端可以忽略
+    fn next_subpass(&mut self) {}
+
+    ///自动处理资源同步的后端可以忽略
+    fn pipeline_barrier(&mut self, _barriers: &[ResourceBarrier]) {}
+
+    ///写入本帧第 index 个时间戳查询，DeviceTrait::supports_timestamps 为 true 时才会调用
+    fn write_timestamp(&mut self, _index: u32) {}
+
+    ///开始一个调试分组，在 GPU 调试工具中显示为 label，必须与 pop_debug_group 成对调用
+    fn push_debug_group(&mut self, _label: &str) {}
+
+    fn pop_debug_group(&mut self) {}
+
+    ///插入一个调试标记
+    fn insert_debug_marker(&mut self, _label: &str) {}
 }
 
 pub trait ErasedCommandBufferTrait: 'static + Sync + Send + Debug + Downcast {
     fn begin_render_pass(&mut self, render_pass: RenderPass);
 
     fn end_render_pass(&mut self);
+
+    fn begin_compute_pass(&mut self);
+
+    fn end_compute_pass(&mut self);
+
+    fn copy_texture_to_texture(&mut self, source: &Texture, destination: &Texture, size: Extent3d);
+
+    fn copy_texture_to_buffer(
+        &mut self,
+        source: &Texture,
+        destination: &Buffer,
+        layout: TexelCopyBufferLayout,
+        size: Extent3d,
+    );
+
+    fn copy_buffer_to_buffer(
+        &mut self,
+        source: &Buffer,
+        source_offset: u64,
+        destination: &Buffer,
+        destination_offset: u64,
+        size: u64,
+    );
+
+    fn next_subpass(&mut self);
+
+    fn pipeline_barrier(&mut self, barriers: &[ResourceBarrier]);
+
+    fn write_timestamp(&mut self, index: u32);
+
+    fn push_debug_group(&mut self, label: &str);
+
+    fn pop_debug_group(&mut self);
+
+    fn insert_debug_marker(&mut self, label: &str);
 }
 
 impl<T> ErasedCommandBufferTrait for T
@@ -31,6 +144,70 @@ where
     fn end_render_pass(&mut self) {
         <T as CommandBufferTrait>::end_render_pass(self);
     }
+
+    fn begin_compute_pass(&mut self) {
+        <T as CommandBufferTrait>::begin_compute_pass(self);
+    }
+
+    fn end_compute_pass(&mut self) {
+        <T as CommandBufferTrait>::end_compute_pass(self);
+    }
+
+    fn copy_texture_to_texture(&mut self, source: &Texture, destination: &Texture, size: Extent3d) {
+        <T as CommandBufferTrait>::copy_texture_to_texture(self, source, destination, size);
+    }
+
+    fn copy_texture_to_buffer(
+        &mut self,
+        source: &Texture,
+        destination: &Buffer,
+        layout: TexelCopyBufferLayout,
+        size: Extent3d,
+    ) {
+        <T as CommandBufferTrait>::copy_texture_to_buffer(self, source, destination, layout, size);
+    }
+
+    fn copy_buffer_to_buffer(
+        &mut self,
+        source: &Buffer,
+        source_offset: u64,
+        destination: &Buffer,
+        destination_offset: u64,
+        size: u64,
+    ) {
+        <T as CommandBufferTrait>::copy_buffer_to_buffer(
+            self,
+            source,
+            source_offset,
+            destination,
+            destination_offset,
+            size,
+        );
+    }
+
+    fn next_subpass(&mut self) {
+        <T as CommandBufferTrait>::next_subpass(self);
+    }
+
+    fn pipeline_barrier(&mut self, barriers: &[ResourceBarrier]) {
+        <T as CommandBufferTrait>::pipeline_barrier(self, barriers);
+    }
+
+    fn write_timestamp(&mut self, index: u32) {
+        <T as CommandBufferTrait>::write_timestamp(self, index);
+    }
+
+    fn push_debug_group(&mut self, label: &str) {
+        <T as CommandBufferTrait>::push_debug_group(self, label);
+    }
+
+    fn pop_debug_group(&mut self) {
+        <T as CommandBufferTrait>::pop_debug_group(self);
+    }
+
+    fn insert_debug_marker(&mut self, label: &str) {
+        <T as CommandBufferTrait>::insert_debug_marker(self, label);
+    }
 }
 
 define_gfx_type!(
@@ -48,4 +225,74 @@ impl CommandBuffer {
     pub fn end_render_pass(&mut self) {
         self.value.end_render_pass();
     }
+
+    pub fn begin_compute_pass(&mut self) {
+        self.value.begin_compute_pass();
+    }
+
+    pub fn end_compute_pass(&mut self) {
+        self.value.end_compute_pass();
+    }
+
+    pub fn copy_texture_to_texture(
+        &mut self,
+        source: &Texture,
+        destination: &Texture,
+        size: Extent3d,
+    ) {
+        self.value
+            .copy_texture_to_texture(source, destination, size);
+    }
+
+    pub fn copy_texture_to_buffer(
+        &mut self,
+        source: &Texture,
+        destination: &Buffer,
+        layout: TexelCopyBufferLayout,
+        size: Extent3d,
+    ) {
+        self.value
+            .copy_texture_to_buffer(source, destination, layout, size);
+    }
+
+    pub fn copy_buffer_to_buffer(
+        &mut self,
+        source: &Buffer,
+        source_offset: u64,
+        destination: &Buffer,
+        destination_offset: u64,
+        size: u64,
+    ) {
+        self.value.copy_buffer_to_buffer(
+            source,
+            source_offset,
+            destination,
+            destination_offset,
+            size,
+        );
+    }
+
+    pub fn next_subpass(&mut self) {
+        self.value.next_subpass();
+    }
+
+    pub fn pipeline_barrier(&mut self, barriers: &[ResourceBarrier]) {
+        self.value.pipeline_barrier(barriers);
+    }
+
+    pub fn write_timestamp(&mut self, index: u32) {
+        self.value.write_timestamp(index);
+    }
+
+    pub fn push_debug_group(&mut self, label: &str) {
+        self.value.push_debug_group(label);
+    }
+
+    pub fn pop_debug_group(&mut self) {
+        self.value.pop_debug_group();
+    }
+
+    pub fn insert_debug_marker(&mut self, label: &str) {
+        self.value.insert_debug_marker(label);
+    }
 }
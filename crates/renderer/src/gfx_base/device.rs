@@ -3,31 +3,145 @@ use std::fmt::Debug;
 
 use downcast_rs::Downcast;
 
-use super::{CommandBuffer, RenderPass, RenderPassInfo};
+use super::{
+    Buffer, BufferDescriptor, CommandBuffer, QueueSubmission, QueueType, RenderPass,
+    RenderPassInfo, Texture, TextureDescriptor,
+};
 
 define_atomic_id!(DeviceId);
 
+pub type BufferReadFn = Box<dyn FnOnce(Vec<u8>) + Send>;
+
 pub trait DeviceTrait: 'static + Sync + Send + Debug {
     fn create_command_buffer(&self) -> CommandBuffer;
 
     fn create_render_pass(&self, desc: RenderPassInfo) -> RenderPass;
 
+    fn create_texture(&self, desc: TextureDescriptor) -> Texture;
+
+    fn create_buffer(&self, desc: BufferDescriptor) -> Buffer;
+
+    ///创建带有调试名称的纹理，不支持调试名称的后端可以忽略 label
+    fn create_texture_with_label(&self, desc: TextureDescriptor, _label: &str) -> Texture {
+        self.create_texture(desc)
+    }
+
+    ///创建带有调试名称的缓冲区，不支持调试名称的后端可以忽略 label
+    fn create_buffer_with_label(&self, desc: BufferDescriptor, _label: &str) -> Buffer {
+        self.create_buffer(desc)
+    }
+
     fn submit(&self, command_buffers: Vec<CommandBuffer>);
+
+    ///等待已提交的命令执行完成后读取缓冲区的内容
+    fn read_buffer(&self, buffer: &Buffer) -> Vec<u8>;
+
+    ///已提交的命令执行完成后把缓冲区的内容交给 callback，不需要等待。
+    ///默认调用 read_buffer 阻塞等待
+    fn read_buffer_async(&self, buffer: Buffer, callback: BufferReadFn) {
+        callback(self.read_buffer(&buffer))
+    }
+
+    ///设备支持的队列，只有图形队列时所有命令都提交到图形队列
+    fn queues(&self) -> Vec<QueueType> {
+        vec![QueueType::Graphics]
+    }
+
+    ///默认忽略队列和同步点，按提交顺序执行
+    fn submit_queue(&self, submission: QueueSubmission) {
+        self.submit(submission.command_buffers);
+    }
+
+    ///是否支持 GPU 时间戳查询
+    fn supports_timestamps(&self) -> bool {
+        false
+    }
+
+    ///等待已提交的命令执行完成后读取本帧写入的前 count 个时间戳，单位为纳秒
+    fn read_timestamps(&self, _count: u32) -> Vec<u64> {
+        vec![]
+    }
 }
 
 pub trait ErasedDeviceTrait: 'static + Sync + Send + Debug + Downcast {
     fn create_command_buffer(&self) -> CommandBuffer;
 
     fn create_render_pass(&self, desc: RenderPassInfo) -> RenderPass;
+
+    fn create_texture(&self, desc: TextureDescriptor) -> Texture;
+
+    fn create_buffer(&self, desc: BufferDescriptor) -> Buffer;
+
+    fn create_texture_with_label(&self, desc: TextureDescriptor, label: &str) -> Texture;
+
+    fn create_buffer_with_label(&self, desc: BufferDescriptor, label: &str) -> Buffer;
+
+    fn submit(&self, command_buffers: Vec<CommandBuffer>);
+
+    fn read_buffer(&self, buffer: &Buffer) -> Vec<u8>;
+
+    fn read_buffer_async(&self, buffer: Buffer, callback: BufferReadFn);
+
+    fn queues(&self) -> Vec<QueueType>;
+
+    fn submit_queue(&self, submission: QueueSubmission);
+
+    fn supports_timestamps(&self) -> bool;
+
+    fn read_timestamps(&self, count: u32) -> Vec<u64>;
 }
 
 impl<T: DeviceTrait> ErasedDeviceTrait for T {
     fn create_command_buffer(&self) -> CommandBuffer {
-        <T as DeviceTrait>::create_command_buffer(&self)
+        <T as DeviceTrait>::create_command_buffer(self)
     }
 
     fn create_render_pass(&self, desc: RenderPassInfo) -> RenderPass {
-        <T as DeviceTrait>::create_render_pass(&self, desc)
+        <T as DeviceTrait>::create_render_pass(self, desc)
+    }
+
+    fn create_texture(&self, desc: TextureDescriptor) -> Texture {
+        <T as DeviceTrait>::create_texture(self, desc)
+    }
+
+    fn create_buffer(&self, desc: BufferDescriptor) -> Buffer {
+        <T as DeviceTrait>::create_buffer(self, desc)
+    }
+
+    fn create_texture_with_label(&self, desc: TextureDescriptor, label: &str) -> Texture {
+        <T as DeviceTrait>::create_texture_with_label(self, desc, label)
+    }
+
+    fn create_buffer_with_label(&self, desc: BufferDescriptor, label: &str) -> Buffer {
+        <T as DeviceTrait>::create_buffer_with_label(self, desc, label)
+    }
+
+    fn submit(&self, command_buffers: Vec<CommandBuffer>) {
+        <T as DeviceTrait>::submit(self, command_buffers)
+    }
+
+    fn read_buffer(&self, buffer: &Buffer) -> Vec<u8> {
+        <T as DeviceTrait>::read_buffer(self, buffer)
+    }
+
+    fn read_buffer_async(&self, buffer: Buffer, callback: BufferReadFn) {
+        <T as DeviceTrait>::read_buffer_async(self, buffer, callback)
+    }
+
+    fn queues(&self) -> Vec<QueueType> {
+        <T as DeviceTrait>::queues(self)
+    }
+
+    fn submit_queue(&self, submission: QueueSubmission) {
+        <T as DeviceTrait>::submit_queue(self, submission)
+    }
+
+    fn supports_timestamps(&self) -> bool {
+        <T as DeviceTrait>::supports_timestamps(self)
+    }
+
+    fn read_timestamps(&self, count: u32) -> Vec<u64> {
+        <T as DeviceTrait>::read_timestamps(self, count)
     }
 }
 
@@ -41,4 +155,48 @@ impl Device {
     pub fn create_render_pass(&self, desc: RenderPassInfo) -> RenderPass {
         self.value.create_render_pass(desc)
     }
+
+    pub fn create_texture(&self, desc: TextureDescriptor) -> Texture {
+        self.value.create_texture(desc)
+    }
+
+    pub fn create_buffer(&self, desc: BufferDescriptor) -> Buffer {
+        self.value.create_buffer(desc)
+    }
+
+    pub fn create_texture_with_label(&self, desc: TextureDescriptor, label: &str) -> Texture {
+        self.value.create_texture_with_label(desc, label)
+    }
+
+    pub fn create_buffer_with_label(&self, desc: BufferDescriptor, label: &str) -> Buffer {
+        self.value.create_buffer_with_label(desc, label)
+    }
+
+    pub fn submit(&self, command_buffers: Vec<CommandBuffer>) {
+        self.value.submit(command_buffers)
+    }
+
+    pub fn read_buffer(&self, buffer: &Buffer) -> Vec<u8> {
+        self.value.read_buffer(buffer)
+    }
+
+    pub fn read_buffer_async(&self, buffer: Buffer, callback: BufferReadFn) {
+        self.value.read_buffer_async(buffer, callback)
+    }
+
+    pub fn queues(&self) -> Vec<QueueType> {
+        self.value.queues()
+    }
+
+    pub fn submit_queue(&self, submission: QueueSubmission) {
+        self.value.submit_queue(submission)
+    }
+
+    pub fn supports_timestamps(&self) -> bool {
+        self.value.supports_timestamps()
+    }
+
+    pub fn read_timestamps(&self, count: u32) -> Vec<u64> {
+        self.value.read_timestamps(count)
+    }
 }
@@ -1,15 +1,19 @@
-use std::{any::TypeId, hash::Hash, marker::PhantomData};
+use std::{any::TypeId, fmt::Debug, hash::Hash, marker::PhantomData};
 
 //类型索引
+//比较和哈希只使用索引，代数由持有者在查找时检查
 pub struct TypeHandle<T> {
     index: usize,
-    _marker: PhantomData<T>,
+    generation: u32,
+    //句柄不持有 T，总是可以在线程之间传递
+    _marker: PhantomData<fn() -> T>,
 }
 
 impl<T> Default for TypeHandle<T> {
     fn default() -> Self {
         TypeHandle {
             index: Self::UNINITIALIZED,
+            generation: 0,
             _marker: PhantomData,
         }
     }
@@ -23,8 +27,13 @@ impl<T> TypeHandle<T> {
     }
 
     pub fn new(index: usize) -> Self {
+        Self::with_generation(index, 0)
+    }
+
+    pub fn with_generation(index: usize, generation: u32) -> Self {
         TypeHandle {
             index,
+            generation,
             _marker: PhantomData,
         }
     }
@@ -32,6 +41,19 @@ impl<T> TypeHandle<T> {
     pub fn index(&self) -> usize {
         self.index
     }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl<T> Debug for TypeHandle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("TypeHandle")
+            .field(&self.index)
+            .field(&self.generation)
+            .finish()
+    }
 }
 
 impl<T> PartialEq for TypeHandle<T> {
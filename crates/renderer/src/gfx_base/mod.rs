@@ -1,3 +1,5 @@
+mod barrier;
+mod buffer;
 mod command_buffer;
 mod common;
 mod device;
@@ -5,10 +7,14 @@ mod handle;
 mod texture;
 mod render_pass;
 mod macros;
+mod queue;
 
+pub use barrier::*;
+pub use buffer::*;
 pub use command_buffer::*;
 pub use common::*;
 pub use device::*;
 pub use handle::*;
+pub use queue::*;
 pub use texture::*;
 pub use render_pass::*;
@@ -0,0 +1,33 @@
+use serde::Serialize;
+
+use super::CommandBuffer;
+
+///命令提交到的队列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueType {
+    #[default]
+    Graphics,
+    ///异步计算队列
+    Compute,
+    ///异步复制队列
+    Transfer,
+}
+
+///队列上的同步点，队列发出第 value 个信号时到达
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SyncPoint {
+    pub queue: QueueType,
+    pub value: u64,
+}
+
+///一次提交到队列的命令
+#[derive(Debug)]
+pub struct QueueSubmission {
+    pub queue: QueueType,
+    ///执行之前需要等待的其他队列的同步点
+    pub waits: Vec<SyncPoint>,
+    pub command_buffers: Vec<CommandBuffer>,
+    ///执行完成之后发出的信号
+    pub signal: Option<SyncPoint>,
+}
@@ -4,12 +4,95 @@ use std::fmt::Debug;
 
 use crate::frame_graph::RenderContext;
 
+use super::{Texture, TextureSubresourceRange};
+
 define_atomic_id!(RenderPassId);
 
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Color {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub a: f64,
+}
+
+impl Color {
+    pub const TRANSPARENT: Color = Color {
+        r: 0.0,
+        g: 0.0,
+        b: 0.0,
+        a: 0.0,
+    };
+
+    pub const BLACK: Color = Color {
+        r: 0.0,
+        g: 0.0,
+        b: 0.0,
+        a: 1.0,
+    };
+
+    pub const WHITE: Color = Color {
+        r: 1.0,
+        g: 1.0,
+        b: 1.0,
+        a: 1.0,
+    };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoadOp<V> {
+    Clear(V),
+    Load,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreOp {
+    Store,
+    Discard,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Operations<V> {
+    pub load: LoadOp<V>,
+    pub store: StoreOp,
+}
+
+impl<V: Default> Default for Operations<V> {
+    fn default() -> Self {
+        Operations {
+            load: LoadOp::Clear(V::default()),
+            store: StoreOp::Store,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ColorAttachment<'a> {
+    pub view: &'a Texture,
+    ///渲染到的 mip 层级和数组层
+    pub range: TextureSubresourceRange,
+    ///多重采样纹理解析到的目标
+    pub resolve_target: Option<&'a Texture>,
+    pub ops: Operations<Color>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DepthStencilAttachment<'a> {
+    pub view: &'a Texture,
+    pub range: TextureSubresourceRange,
+    ///为空时深度只读
+    pub depth_ops: Option<Operations<f32>>,
+    ///为空时模板只读
+    pub stencil_ops: Option<Operations<u32>>,
+}
+
 #[derive(Default, Clone, Debug)]
-pub struct RenderPassInfo {}
+pub struct RenderPassInfo<'a> {
+    pub color_attachments: Vec<ColorAttachment<'a>>,
+    pub depth_stencil_attachment: Option<DepthStencilAttachment<'a>>,
+}
 
-impl RenderPassInfo {
+impl RenderPassInfo<'_> {
     pub fn new() -> Self {
         RenderPassInfo::default()
     }
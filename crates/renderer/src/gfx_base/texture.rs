@@ -1,13 +1,346 @@
-#[derive(Debug)]
-pub struct Texture {
-    desc: TextureDescriptor,
+use crate::{define_atomic_id, define_gfx_frame_graph_type};
+use bitflags::bitflags;
+use downcast_rs::Downcast;
+use std::fmt::Debug;
+
+define_atomic_id!(TextureId);
+
+pub trait TextureTrait: 'static + Sync + Send + Debug {}
+
+pub trait ErasedTextureTrait: 'static + Sync + Send + Debug + Downcast {}
+
+impl<T: TextureTrait> ErasedTextureTrait for T {}
+
+define_gfx_frame_graph_type!(
+    Texture,
+    TextureId,
+    TextureTrait,
+    ErasedTextureTrait,
+    TextureDescriptor
+);
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Extent3d {
+    pub width: u32,
+    pub height: u32,
+    pub depth_or_array_layers: u32,
+}
+
+impl Default for Extent3d {
+    fn default() -> Self {
+        Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub enum TextureDimension {
+    D1,
+    #[default]
+    D2,
+    D3,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub enum TextureFormat {
+    R8Unorm,
+    Rg8Unorm,
+    #[default]
+    Rgba8Unorm,
+    Rgba8UnormSrgb,
+    Bgra8Unorm,
+    Bgra8UnormSrgb,
+    Rgb10a2Unorm,
+    Rg11b10Ufloat,
+    R16Float,
+    Rg16Float,
+    Rgba16Float,
+    R32Float,
+    Rg32Float,
+    Rgba32Float,
+    Depth16Unorm,
+    Depth24Plus,
+    Depth24PlusStencil8,
+    Depth32Float,
+    Depth32FloatStencil8,
+}
+
+impl TextureFormat {
+    ///每个像素占用的字节数
+    pub fn block_size(&self) -> u32 {
+        match self {
+            TextureFormat::R8Unorm => 1,
+            TextureFormat::Rg8Unorm | TextureFormat::R16Float | TextureFormat::Depth16Unorm => 2,
+            TextureFormat::Rgba8Unorm
+            | TextureFormat::Rgba8UnormSrgb
+            | TextureFormat::Bgra8Unorm
+            | TextureFormat::Bgra8UnormSrgb
+            | TextureFormat::Rgb10a2Unorm
+            | TextureFormat::Rg11b10Ufloat
+            | TextureFormat::Rg16Float
+            | TextureFormat::R32Float
+            | TextureFormat::Depth24Plus
+            | TextureFormat::Depth24PlusStencil8
+            | TextureFormat::Depth32Float => 4,
+            TextureFormat::Rgba16Float
+            | TextureFormat::Rg32Float
+            | TextureFormat::Depth32FloatStencil8 => 8,
+            TextureFormat::Rgba32Float => 16,
+        }
+    }
+}
+
+bitflags! {
+    #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+    pub struct TextureUsages: u32 {
+        const COPY_SRC = 1 << 0;
+        const COPY_DST = 1 << 1;
+        const TEXTURE_BINDING = 1 << 2;
+        const STORAGE_BINDING = 1 << 3;
+        const RENDER_ATTACHMENT = 1 << 4;
+    }
+}
+
+///纹理的 mip 层级和数组层范围，数量为空时表示直到最后一层
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub struct TextureSubresourceRange {
+    pub base_mip_level: u32,
+    pub mip_level_count: Option<u32>,
+    pub base_array_layer: u32,
+    pub array_layer_count: Option<u32>,
+}
+
+impl TextureSubresourceRange {
+    pub const ALL: TextureSubresourceRange = TextureSubresourceRange {
+        base_mip_level: 0,
+        mip_level_count: None,
+        base_array_layer: 0,
+        array_layer_count: None,
+    };
+
+    ///所有数组层的一个 mip 层级
+    pub fn mip_level(level: u32) -> Self {
+        TextureSubresourceRange {
+            base_mip_level: level,
+            mip_level_count: Some(1),
+            ..TextureSubresourceRange::ALL
+        }
+    }
+
+    ///所有 mip 层级的一个数组层，立方体贴图的面也使用数组层表示
+    pub fn array_layer(layer: u32) -> Self {
+        TextureSubresourceRange {
+            base_array_layer: layer,
+            array_layer_count: Some(1),
+            ..TextureSubresourceRange::ALL
+        }
+    }
+
+    pub fn is_all(&self) -> bool {
+        *self == TextureSubresourceRange::ALL
+    }
+
+    pub fn overlaps(&self, other: &TextureSubresourceRange) -> bool {
+        range_overlaps(
+            (self.base_mip_level, self.mip_level_count),
+            (other.base_mip_level, other.mip_level_count),
+        ) && range_overlaps(
+            (self.base_array_layer, self.array_layer_count),
+            (other.base_array_layer, other.array_layer_count),
+        )
+    }
+
+    ///把直到最后一层的范围转换为具体的数量
+    pub fn resolve(&self, mip_level_count: u32, array_layer_count: u32) -> TextureSubresourceRange {
+        TextureSubresourceRange {
+            base_mip_level: self.base_mip_level,
+            mip_level_count: Some(
+                self.mip_level_count
+                    .unwrap_or(mip_level_count.saturating_sub(self.base_mip_level)),
+            ),
+            base_array_layer: self.base_array_layer,
+            array_layer_count: Some(
+                self.array_layer_count
+                    .unwrap_or(array_layer_count.saturating_sub(self.base_array_layer)),
+            ),
+        }
+    }
+
+    pub fn intersection(&self, other: &TextureSubresourceRange) -> Option<TextureSubresourceRange> {
+        if !self.overlaps(other) {
+            return None;
+        }
+
+        let (base_mip_level, mip_level_count) = range_intersection(
+            (self.base_mip_level, self.mip_level_count),
+            (other.base_mip_level, other.mip_level_count),
+        );
+        let (base_array_layer, array_layer_count) = range_intersection(
+            (self.base_array_layer, self.array_layer_count),
+            (other.base_array_layer, other.array_layer_count),
+        );
+
+        Some(TextureSubresourceRange {
+            base_mip_level,
+            mip_level_count,
+            base_array_layer,
+            array_layer_count,
+        })
+    }
+
+    ///从范围中去掉 other，剩余部分最多分成四个范围
+    pub fn subtract(&self, other: &TextureSubresourceRange) -> Vec<TextureSubresourceRange> {
+        let Some(overlap) = self.intersection(other) else {
+            return vec![*self];
+        };
+
+        let mips = (
+            self.base_mip_level,
+            range_end((self.base_mip_level, self.mip_level_count)),
+        );
+        let layers = (
+            self.base_array_layer,
+            range_end((self.base_array_layer, self.array_layer_count)),
+        );
+        let overlap_mips = (
+            overlap.base_mip_level,
+            range_end((overlap.base_mip_level, overlap.mip_level_count)),
+        );
+        let overlap_layers = (
+            overlap.base_array_layer,
+            range_end((overlap.base_array_layer, overlap.array_layer_count)),
+        );
+
+        let mut parts = vec![];
+
+        //在 overlap 之前和之后的 mip 层级包含所有数组层
+        if mips.0 < overlap_mips.0 {
+            parts.push(from_bounds((mips.0, Some(overlap_mips.0)), layers));
+        }
+        if let Some(end) = overlap_mips.1
+            && mips.1.is_none_or(|mip_end| end < mip_end)
+        {
+            parts.push(from_bounds((end, mips.1), layers));
+        }
+
+        //与 overlap 相同的 mip 层级只包含 overlap 之外的数组层
+        if layers.0 < overlap_layers.0 {
+            parts.push(from_bounds(
+                overlap_mips,
+                (layers.0, Some(overlap_layers.0)),
+            ));
+        }
+        if let Some(end) = overlap_layers.1
+            && layers.1.is_none_or(|layer_end| end < layer_end)
+        {
+            parts.push(from_bounds(overlap_mips, (end, layers.1)));
+        }
+
+        parts
+    }
+
+    ///同时包含两个范围的最小范围
+    pub fn union(&self, other: &TextureSubresourceRange) -> TextureSubresourceRange {
+        let (base_mip_level, mip_level_count) = range_union(
+            (self.base_mip_level, self.mip_level_count),
+            (other.base_mip_level, other.mip_level_count),
+        );
+        let (base_array_layer, array_layer_count) = range_union(
+            (self.base_array_layer, self.array_layer_count),
+            (other.base_array_layer, other.array_layer_count),
+        );
+
+        TextureSubresourceRange {
+            base_mip_level,
+            mip_level_count,
+            base_array_layer,
+            array_layer_count,
+        }
+    }
+}
+
+fn range_end((base, count): (u32, Option<u32>)) -> Option<u32> {
+    count.map(|count| base.saturating_add(count))
 }
 
-impl Texture {
-    pub fn get_desc(&self) -> &TextureDescriptor {
-        &self.desc
+fn range_overlaps(a: (u32, Option<u32>), b: (u32, Option<u32>)) -> bool {
+    range_end(b).is_none_or(|end| a.0 < end) && range_end(a).is_none_or(|end| b.0 < end)
+}
+
+fn range_intersection(a: (u32, Option<u32>), b: (u32, Option<u32>)) -> (u32, Option<u32>) {
+    let base = a.0.max(b.0);
+    let end = match (range_end(a), range_end(b)) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (end, None) | (None, end) => end,
+    };
+
+    (base, end.map(|end| end - base))
+}
+
+fn from_bounds(mips: (u32, Option<u32>), layers: (u32, Option<u32>)) -> TextureSubresourceRange {
+    TextureSubresourceRange {
+        base_mip_level: mips.0,
+        mip_level_count: mips.1.map(|end| end - mips.0),
+        base_array_layer: layers.0,
+        array_layer_count: layers.1.map(|end| end - layers.0),
     }
 }
 
+fn range_union(a: (u32, Option<u32>), b: (u32, Option<u32>)) -> (u32, Option<u32>) {
+    let base = a.0.min(b.0);
+    let end = range_end(a).zip(range_end(b)).map(|(a, b)| a.max(b));
+
+    (base, end.map(|end| end - base))
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
-pub struct TextureDescriptor;
+pub struct TextureDescriptor {
+    pub size: Extent3d,
+    pub mip_level_count: u32,
+    pub sample_count: u32,
+    pub dimension: TextureDimension,
+    pub format: TextureFormat,
+    pub usage: TextureUsages,
+}
+
+impl Default for TextureDescriptor {
+    fn default() -> Self {
+        TextureDescriptor {
+            size: Extent3d::default(),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::default(),
+            format: TextureFormat::default(),
+            usage: TextureUsages::empty(),
+        }
+    }
+}
+
+impl TextureDescriptor {
+    ///估算纹理占用的显存大小，包含所有 mip 层级
+    pub fn size_in_bytes(&self) -> u64 {
+        let layers = match self.dimension {
+            TextureDimension::D3 => 1,
+            _ => self.size.depth_or_array_layers,
+        };
+
+        (0..self.mip_level_count)
+            .map(|level| {
+                let width = (self.size.width >> level).max(1) as u64;
+                let height = (self.size.height >> level).max(1) as u64;
+                let depth = match self.dimension {
+                    TextureDimension::D3 => (self.size.depth_or_array_layers >> level).max(1),
+                    _ => 1,
+                } as u64;
+
+                width * height * depth
+            })
+            .sum::<u64>()
+            * layers as u64
+            * self.sample_count as u64
+            * self.format.block_size() as u64
+    }
+}
@@ -0,0 +1,39 @@
+use crate::{BufferDescriptor, BufferTrait, BufferUsages};
+
+#[derive(Debug)]
+pub struct WgpuBuffer {
+    pub buffer: wgpu::Buffer,
+}
+
+impl BufferTrait for WgpuBuffer {}
+
+pub fn to_wgpu_buffer_descriptor<'a>(
+    desc: &BufferDescriptor,
+    label: Option<&'a str>,
+) -> wgpu::BufferDescriptor<'a> {
+    wgpu::BufferDescriptor {
+        label,
+        size: desc.size,
+        usage: to_wgpu_buffer_usages(desc.usage),
+        mapped_at_creation: false,
+    }
+}
+
+pub fn to_wgpu_buffer_usages(usage: BufferUsages) -> wgpu::BufferUsages {
+    [
+        (BufferUsages::MAP_READ, wgpu::BufferUsages::MAP_READ),
+        (BufferUsages::MAP_WRITE, wgpu::BufferUsages::MAP_WRITE),
+        (BufferUsages::COPY_SRC, wgpu::BufferUsages::COPY_SRC),
+        (BufferUsages::COPY_DST, wgpu::BufferUsages::COPY_DST),
+        (BufferUsages::INDEX, wgpu::BufferUsages::INDEX),
+        (BufferUsages::VERTEX, wgpu::BufferUsages::VERTEX),
+        (BufferUsages::UNIFORM, wgpu::BufferUsages::UNIFORM),
+        (BufferUsages::STORAGE, wgpu::BufferUsages::STORAGE),
+        (BufferUsages::INDIRECT, wgpu::BufferUsages::INDIRECT),
+    ]
+    .into_iter()
+    .filter(|(flag, _)| usage.contains(*flag))
+    .fold(wgpu::BufferUsages::empty(), |usages, (_, flag)| {
+        usages | flag
+    })
+}
@@ -1,10 +1,38 @@
-use crate::DeviceTrait;
+use crate::{BufferReadFn, DeviceTrait};
+
+use super::{WgpuBuffer, WgpuTexture, to_wgpu_buffer_descriptor, to_wgpu_texture_descriptor};
 
 #[derive(Debug)]
 pub struct WgpuDevice {
     pub device: wgpu::Device,
 }
 
+impl WgpuDevice {
+    fn create_labeled_texture(
+        &self,
+        desc: crate::TextureDescriptor,
+        label: Option<&str>,
+    ) -> crate::Texture {
+        let texture = self
+            .device
+            .create_texture(&to_wgpu_texture_descriptor(&desc, label));
+
+        crate::Texture::new(WgpuTexture { texture }, desc)
+    }
+
+    fn create_labeled_buffer(
+        &self,
+        desc: crate::BufferDescriptor,
+        label: Option<&str>,
+    ) -> crate::Buffer {
+        let buffer = self
+            .device
+            .create_buffer(&to_wgpu_buffer_descriptor(&desc, label));
+
+        crate::Buffer::new(WgpuBuffer { buffer }, desc)
+    }
+}
+
 impl DeviceTrait for WgpuDevice {
     fn create_command_buffer(&self) -> crate::CommandBuffer {
         todo!()
@@ -17,4 +45,79 @@ impl DeviceTrait for WgpuDevice {
     fn create_render_pass(&self, _desc: crate::RenderPassInfo) -> crate::RenderPass {
         todo!()
     }
+
+    fn create_texture(&self, desc: crate::TextureDescriptor) -> crate::Texture {
+        self.create_labeled_texture(desc, None)
+    }
+
+    fn create_buffer(&self, desc: crate::BufferDescriptor) -> crate::Buffer {
+        self.create_labeled_buffer(desc, None)
+    }
+
+    ///label 在 GPU 调试工具中作为纹理的名称
+    fn create_texture_with_label(
+        &self,
+        desc: crate::TextureDescriptor,
+        label: &str,
+    ) -> crate::Texture {
+        self.create_labeled_texture(desc, Some(label))
+    }
+
+    fn create_buffer_with_label(
+        &self,
+        desc: crate::BufferDescriptor,
+        label: &str,
+    ) -> crate::Buffer {
+        self.create_labeled_buffer(desc, Some(label))
+    }
+
+    ///缓冲区不是由此设备创建或者映射失败时返回空数据
+    fn read_buffer(&self, buffer: &crate::Buffer) -> Vec<u8> {
+        let Some(buffer) = buffer.downcast_ref::<WgpuBuffer>() else {
+            return vec![];
+        };
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        buffer
+            .buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        self.device.poll(wgpu::Maintain::Wait);
+
+        match receiver.recv() {
+            Ok(Ok(())) => read_mapped(&buffer.buffer),
+            _ => vec![],
+        }
+    }
+
+    ///命令缓冲区还没有实现，不能写入时间戳查询，FrameTimingReport 只有 CPU 时间
+    fn supports_timestamps(&self) -> bool {
+        false
+    }
+
+    ///映射完成的回调在 wgpu::Device::poll 或者之后的提交中执行
+    fn read_buffer_async(&self, buffer: crate::Buffer, callback: BufferReadFn) {
+        let Some(buffer) = buffer.downcast_ref::<WgpuBuffer>() else {
+            callback(vec![]);
+            return;
+        };
+
+        let mapped = buffer.buffer.clone();
+        buffer
+            .buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| match result {
+                Ok(()) => callback(read_mapped(&mapped)),
+                Err(_) => callback(vec![]),
+            });
+    }
+}
+
+fn read_mapped(buffer: &wgpu::Buffer) -> Vec<u8> {
+    let data = buffer.slice(..).get_mapped_range().to_vec();
+    buffer.unmap();
+
+    data
 }
@@ -1,3 +1,7 @@
+pub mod buffer;
 pub mod device;
+pub mod texture;
 
-pub use device::*;
\ No newline at end of file
+pub use buffer::*;
+pub use device::*;
+pub use texture::*;
@@ -0,0 +1,90 @@
+use crate::{
+    Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureTrait, TextureUsages,
+};
+
+#[derive(Debug)]
+pub struct WgpuTexture {
+    pub texture: wgpu::Texture,
+}
+
+impl TextureTrait for WgpuTexture {}
+
+pub fn to_wgpu_texture_descriptor<'a>(
+    desc: &TextureDescriptor,
+    label: Option<&'a str>,
+) -> wgpu::TextureDescriptor<'a> {
+    wgpu::TextureDescriptor {
+        label,
+        size: to_wgpu_extent_3d(desc.size),
+        mip_level_count: desc.mip_level_count,
+        sample_count: desc.sample_count,
+        dimension: to_wgpu_texture_dimension(desc.dimension),
+        format: to_wgpu_texture_format(desc.format),
+        usage: to_wgpu_texture_usages(desc.usage),
+        view_formats: &[],
+    }
+}
+
+pub fn to_wgpu_extent_3d(size: Extent3d) -> wgpu::Extent3d {
+    wgpu::Extent3d {
+        width: size.width,
+        height: size.height,
+        depth_or_array_layers: size.depth_or_array_layers,
+    }
+}
+
+pub fn to_wgpu_texture_dimension(dimension: TextureDimension) -> wgpu::TextureDimension {
+    match dimension {
+        TextureDimension::D1 => wgpu::TextureDimension::D1,
+        TextureDimension::D2 => wgpu::TextureDimension::D2,
+        TextureDimension::D3 => wgpu::TextureDimension::D3,
+    }
+}
+
+pub fn to_wgpu_texture_format(format: TextureFormat) -> wgpu::TextureFormat {
+    match format {
+        TextureFormat::R8Unorm => wgpu::TextureFormat::R8Unorm,
+        TextureFormat::Rg8Unorm => wgpu::TextureFormat::Rg8Unorm,
+        TextureFormat::Rgba8Unorm => wgpu::TextureFormat::Rgba8Unorm,
+        TextureFormat::Rgba8UnormSrgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+        TextureFormat::Bgra8Unorm => wgpu::TextureFormat::Bgra8Unorm,
+        TextureFormat::Bgra8UnormSrgb => wgpu::TextureFormat::Bgra8UnormSrgb,
+        TextureFormat::Rgb10a2Unorm => wgpu::TextureFormat::Rgb10a2Unorm,
+        TextureFormat::Rg11b10Ufloat => wgpu::TextureFormat::Rg11b10Ufloat,
+        TextureFormat::R16Float => wgpu::TextureFormat::R16Float,
+        TextureFormat::Rg16Float => wgpu::TextureFormat::Rg16Float,
+        TextureFormat::Rgba16Float => wgpu::TextureFormat::Rgba16Float,
+        TextureFormat::R32Float => wgpu::TextureFormat::R32Float,
+        TextureFormat::Rg32Float => wgpu::TextureFormat::Rg32Float,
+        TextureFormat::Rgba32Float => wgpu::TextureFormat::Rgba32Float,
+        TextureFormat::Depth16Unorm => wgpu::TextureFormat::Depth16Unorm,
+        TextureFormat::Depth24Plus => wgpu::TextureFormat::Depth24Plus,
+        TextureFormat::Depth24PlusStencil8 => wgpu::TextureFormat::Depth24PlusStencil8,
+        TextureFormat::Depth32Float => wgpu::TextureFormat::Depth32Float,
+        TextureFormat::Depth32FloatStencil8 => wgpu::TextureFormat::Depth32FloatStencil8,
+    }
+}
+
+pub fn to_wgpu_texture_usages(usage: TextureUsages) -> wgpu::TextureUsages {
+    [
+        (TextureUsages::COPY_SRC, wgpu::TextureUsages::COPY_SRC),
+        (TextureUsages::COPY_DST, wgpu::TextureUsages::COPY_DST),
+        (
+            TextureUsages::TEXTURE_BINDING,
+            wgpu::TextureUsages::TEXTURE_BINDING,
+        ),
+        (
+            TextureUsages::STORAGE_BINDING,
+            wgpu::TextureUsages::STORAGE_BINDING,
+        ),
+        (
+            TextureUsages::RENDER_ATTACHMENT,
+            wgpu::TextureUsages::RENDER_ATTACHMENT,
+        ),
+    ]
+    .into_iter()
+    .filter(|(flag, _)| usage.contains(*flag))
+    .fold(wgpu::TextureUsages::empty(), |usages, (_, flag)| {
+        usages | flag
+    })
+}